@@ -0,0 +1,44 @@
+//! Regression benchmark for [Lines::position] lookups.
+//!
+//! Run with `cargo bench --bench position`. Lookup time should grow logarithmically with the
+//! number of lines, so the time per lookup should barely change from the smallest input to the
+//! largest, where a linear scan would be about a thousand times slower. Only timings are
+//! reported, since they vary too much between machines to fail on.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use line_position::Lines;
+
+/// Number of lookups timed per input size.
+const LOOKUPS: usize = 100_000;
+
+/// Time `LOOKUPS` position lookups spread over an input of `num_lines` lines.
+fn time_lookups(num_lines: usize) -> Duration {
+    let source = "let x = 1;\n".repeat(num_lines);
+    let lines = Lines::parse(&source);
+    let step = (source.len() / LOOKUPS).max(1);
+
+    let start = Instant::now();
+    for offset in (0..source.len()).step_by(step).cycle().take(LOOKUPS) {
+        black_box(lines.position(black_box(offset)).ok());
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let mut results = Vec::new();
+    for num_lines in [1_000, 10_000, 100_000, 1_000_000] {
+        let elapsed = time_lookups(num_lines);
+        println!(
+            "{num_lines:>9} lines: {:>8.1} ns/lookup",
+            elapsed.as_nanos() as f64 / LOOKUPS as f64
+        );
+        results.push(elapsed);
+    }
+
+    // A thousandfold increase in lines costs about 10 more comparisons per lookup with a binary
+    // search, and a thousandfold increase in time with a linear scan.
+    let ratio = results[3].as_secs_f64() / results[0].as_secs_f64();
+    println!("slowdown from 1k to 1M lines: {ratio:.1}x");
+}
@@ -0,0 +1,150 @@
+//! Zero-copy access to indexes archived with `rkyv`.
+//!
+//! An archived index can be queried in place, such as straight out of a memory-mapped cache
+//! file, without deserializing it first.
+//!
+//! ```
+//! use line_position::{LineIndex, Lines};
+//!
+//! let lines = Lines::parse("abc\ndef\n");
+//! let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&lines).unwrap();
+//! let archived = rkyv::access::<line_position::ArchivedLines, rkyv::rancor::Error>(&bytes).unwrap();
+//! assert_eq!(archived.position(5).unwrap(), lines.position(5).unwrap());
+//! ```
+
+use core::ops::Range;
+
+use crate::{
+    ArchivedLineEnding, ArchivedLines, LineEnding, LineIndex, LinePosition, LinesError,
+    LinesResult, BOM,
+};
+
+impl ArchivedLineEnding {
+    /// Length of the terminator in bytes, as in [LineEnding].
+    fn len(&self) -> usize {
+        match self {
+            ArchivedLineEnding::Lf => LineEnding::Lf,
+            ArchivedLineEnding::Crlf => LineEnding::Crlf,
+            ArchivedLineEnding::Cr => LineEnding::Cr,
+            ArchivedLineEnding::FormFeed => LineEnding::FormFeed,
+            ArchivedLineEnding::VerticalTab => LineEnding::VerticalTab,
+            ArchivedLineEnding::NextLine => LineEnding::NextLine,
+            ArchivedLineEnding::LineSeparator => LineEnding::LineSeparator,
+            ArchivedLineEnding::ParagraphSeparator => LineEnding::ParagraphSeparator,
+        }
+        .len()
+    }
+}
+
+impl ArchivedLines {
+    /// Length in bytes of the parsed input.
+    fn len(&self) -> usize {
+        self.len.to_native() as usize
+    }
+
+    /// Zero-indexed line of a line number, starting with 1.
+    fn index(&self, line: usize) -> Result<usize, LinesError> {
+        line.checked_sub(1)
+            .filter(|&index| index < self.starts.len())
+            .ok_or(LinesError::LineOutOfBounds)
+    }
+
+    fn start(&self, index: usize) -> usize {
+        self.starts[index].to_native() as usize
+    }
+
+    fn end(&self, index: usize) -> usize {
+        self.starts
+            .get(index + 1)
+            .map_or(self.len(), |start| start.to_native() as usize)
+    }
+
+    /// Offset from which columns are counted on a zero-indexed line, as in [Lines](crate::Lines).
+    fn column_start(&self, index: usize) -> usize {
+        match index == 0 && self.bom && self.skip_bom {
+            true => BOM.len(),
+            false => self.start(index),
+        }
+    }
+}
+
+impl LineIndex for ArchivedLines {
+    fn position(&self, offset: usize) -> LinesResult {
+        let index = self
+            .starts
+            .partition_point(|start| start.to_native() as usize <= offset)
+            .saturating_sub(1);
+        match offset < self.len() {
+            true => Ok(LinePosition::new(
+                index + 1,
+                offset.saturating_sub(self.column_start(index)),
+            )),
+            false => Err(LinesError::OffsetOutOfBounds {
+                offset,
+                max: self.len(),
+            }),
+        }
+    }
+
+    fn offset_of(&self, line: usize, column: usize) -> Result<usize, LinesError> {
+        let index = self.index(line)?;
+        let start = self.column_start(index);
+        let terminator = self.endings.get(index).map_or(0, ArchivedLineEnding::len);
+        match column <= (self.end(index) - terminator).saturating_sub(start) {
+            true => Ok(start + column),
+            false => Err(LinesError::ColumnOutOfBounds),
+        }
+    }
+
+    fn num_lines(&self) -> usize {
+        self.starts.len()
+    }
+
+    fn line_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+        let index = self.index(line)?;
+        Ok(self.start(index)..self.end(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lines, ParseOptions};
+    use rkyv::rancor::Error;
+
+    #[test]
+    fn archived_matches_lines() -> Result<(), LinesError> {
+        let input = "\u{FEFF}ab\r\ncd\nef";
+        for lines in [
+            Lines::parse(input),
+            ParseOptions::new().skip_bom(true).parse(input)?,
+            Lines::parse(""),
+        ] {
+            let bytes = rkyv::to_bytes::<Error>(&lines).unwrap();
+            let archived = rkyv::access::<ArchivedLines, Error>(&bytes).unwrap();
+
+            assert_eq!(archived.num_lines(), lines.num_lines());
+            for offset in 0..=input.len() + 1 {
+                assert_eq!(
+                    archived.position(offset).ok(),
+                    lines.position(offset).ok(),
+                    "{offset}"
+                );
+            }
+            for line in 0..=4 {
+                assert_eq!(archived.line_span(line).ok(), lines.line_span(line).ok());
+                for column in 0..4 {
+                    assert_eq!(
+                        archived.offset_of(line, column).ok(),
+                        lines.offset_of(line, column).ok()
+                    );
+                }
+            }
+
+            let deserialized = rkyv::deserialize::<Lines, Error>(archived).unwrap();
+            assert_eq!(deserialized, lines);
+        }
+
+        Ok(())
+    }
+}
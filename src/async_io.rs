@@ -0,0 +1,54 @@
+//! Asynchronous construction of an index using tokio.
+
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{Lines, Scanner};
+
+/// Size of the chunks read at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl Lines {
+    /// Index text read from an asynchronous `reader` without keeping the text, like
+    /// [from_reader](Lines::from_reader).
+    ///
+    /// The reader is read in chunks as they become available, so waiting for a large file does
+    /// not block the runtime. Requires the `tokio` feature.
+    ///
+    /// ```
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::from_async_reader(&b"abc\r\ndef\r\n"[..]).await.unwrap();
+    /// assert_eq!(lines.num_lines(), 2);
+    /// # });
+    /// ```
+    pub async fn from_async_reader(mut reader: impl AsyncRead + Unpin) -> io::Result<Self> {
+        let mut scanner = Scanner::default();
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            match reader.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(len) => scanner.feed(&buffer[..len]),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(scanner.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn matches_parse() -> io::Result<()> {
+        let input = "a\nb\r\n".repeat(CHUNK_SIZE / 3);
+        let lines = Lines::from_async_reader(input.as_bytes()).await?;
+        assert_eq!(lines, Lines::parse(&input));
+
+        Ok(())
+    }
+}
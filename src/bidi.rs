@@ -0,0 +1,122 @@
+//! Visual columns of positions in bidirectional text.
+//!
+//! Requires the `unicode-bidi` feature.
+
+use unicode_bidi::BidiInfo;
+
+use crate::{Lines, LinesError};
+
+impl Lines {
+    /// Visual column of a byte offset within its line, after bidirectional reordering.
+    ///
+    /// The column counts the characters displayed before the character at `offset`, from the
+    /// left, once the line has been reordered for display as described by the Unicode
+    /// Bidirectional Algorithm. In purely left-to-right text this equals the number of
+    /// characters preceding the offset on its line. An offset at the line terminator maps past
+    /// the last character.
+    ///
+    /// `source` must be the text this index was built from, otherwise this may fail with
+    /// [StaleIndex](LinesError::StaleIndex). An offset inside a multi-byte character is treated as
+    /// the start of that character.
+    ///
+    /// Requires the `unicode-bidi` feature.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// // "ab אבג": the Hebrew letters are displayed right to left.
+    /// let source = "ab \u{5d0}\u{5d1}\u{5d2}";
+    /// let lines = Lines::parse(source);
+    /// assert_eq!(lines.visual_column(source, 1).unwrap(), 1);
+    /// assert_eq!(lines.visual_column(source, 3).unwrap(), 5, "alef is displayed rightmost");
+    /// assert_eq!(lines.visual_column(source, 7).unwrap(), 3, "gimel is displayed leftmost");
+    /// ```
+    pub fn visual_column(&self, source: &str, offset: usize) -> Result<usize, LinesError> {
+        let position = self.position(offset)?;
+        let line = self
+            .line(position.line() - 1)
+            .expect("position refers to a parsed line");
+        let start = self.column_start(position.line() - 1);
+        let text = source
+            .get(start..line.content_end)
+            .ok_or(LinesError::StaleIndex)?;
+        let mut target = offset.saturating_sub(start);
+        if target >= text.len() {
+            return Ok(text.chars().count());
+        }
+        while !text.is_char_boundary(target) {
+            target -= 1;
+        }
+
+        let info = BidiInfo::new(text, None);
+        let (levels, runs) = info.visual_runs(&info.paragraphs[0], 0..text.len());
+        let mut column = 0;
+        for run in runs {
+            if !run.contains(&target) {
+                column += text[run].chars().count();
+                continue;
+            }
+            return Ok(match levels[run.start].is_rtl() {
+                true => column + text[target..run.end].chars().count() - 1,
+                false => column + text[run.start..target].chars().count(),
+            });
+        }
+        unreachable!("visual runs cover the line")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_to_right() -> Result<(), LinesError> {
+        let source = "abc\nd\u{e9}f\n";
+        let lines = Lines::parse(source);
+
+        assert_eq!(lines.visual_column(source, 2)?, 2);
+        assert_eq!(lines.visual_column(source, 7)?, 2, "after a two-byte char");
+        assert_eq!(lines.visual_column(source, 8)?, 3, "terminator");
+        assert!(lines.visual_column(source, 9).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mixed_direction() -> Result<(), LinesError> {
+        // The paragraph direction follows the first strong character, so this line is
+        // right-to-left and displays as "x בא" read from the left.
+        let source = "\u{5d0}\u{5d1} x";
+        let lines = Lines::parse(source);
+
+        assert_eq!(lines.visual_column(source, 0)?, 3, "alef rightmost");
+        assert_eq!(lines.visual_column(source, 2)?, 2, "bet left of alef");
+        assert_eq!(lines.visual_column(source, 4)?, 1, "space");
+        assert_eq!(lines.visual_column(source, 5)?, 0, "x leftmost");
+
+        Ok(())
+    }
+
+    #[test]
+    fn skipped_bom() -> Result<(), LinesError> {
+        let source = "\u{FEFF}ab\n";
+        let lines = crate::ParseOptions::new().skip_bom(true).parse(source)?;
+
+        assert_eq!(lines.visual_column(source, 1)?, 0, "inside the BOM");
+        assert_eq!(lines.visual_column(source, 4)?, 1);
+        assert_eq!(lines.visual_column(source, 5)?, 2, "line terminator");
+
+        Ok(())
+    }
+
+    #[test]
+    fn stale_source() {
+        let lines = Lines::parse("ab\ncd");
+        for offset in [3, 4] {
+            assert!(matches!(
+                lines.visual_column("ab", offset),
+                Err(LinesError::StaleIndex)
+            ));
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Indexing of raw bytes that may not be valid UTF-8.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+#[cfg(feature = "bstr")]
+use alloc::borrow::Cow;
+
+#[cfg(feature = "bstr")]
+use bstr::{BStr, ByteSlice};
+
+use crate::Lines;
+#[cfg(feature = "bstr")]
+use crate::LinesError;
+
+/// A run of bytes that failed UTF-8 validation, found by
+/// [parse_bytes_with_errors](Lines::parse_bytes_with_errors).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    range: Range<usize>,
+    line: usize,
+}
+
+impl InvalidUtf8 {
+    /// Byte range of the invalid run.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Line number of the invalid run, starting with 1.
+    ///
+    /// Line terminators are ASCII and therefore valid, so a run never spans lines.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl Lines {
+    /// Parse raw bytes that may not be valid UTF-8, such as logs or files in mixed encodings.
+    ///
+    /// Line terminators are found as in [parse](Lines::parse) and positions are byte offsets,
+    /// so they can be reported even for text that fails [from_utf8](core::str::from_utf8).
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse_bytes(b"ok\n\xff\xfe bad\n");
+    /// let position = lines.position(7).unwrap();
+    /// assert_eq!((position.line(), position.offset()), (2, 4));
+    /// ```
+    pub fn parse_bytes(input: &[u8]) -> Self {
+        Lines::scan(input)
+    }
+
+    /// Parse raw bytes, also collecting every run of bytes that is not valid UTF-8.
+    ///
+    /// Line terminators are found as in [parse](Lines::parse) regardless of validity, so offsets
+    /// and line numbers stay accurate around corrupt data. Adjacent invalid sequences are merged
+    /// into a single run.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let (lines, invalid) = Lines::parse_bytes_with_errors(b"abc\nd\xffe\n");
+    /// assert_eq!(lines.num_lines(), 2);
+    /// assert_eq!(invalid.len(), 1);
+    /// assert_eq!(invalid[0].range(), 5..6);
+    /// assert_eq!(invalid[0].line(), 2);
+    /// ```
+    pub fn parse_bytes_with_errors(input: &[u8]) -> (Self, Vec<InvalidUtf8>) {
+        let lines = Lines::scan(input);
+        let mut invalid: Vec<InvalidUtf8> = Vec::new();
+
+        let mut offset = 0;
+        while let Err(error) = core::str::from_utf8(&input[offset..]) {
+            let start = offset + error.valid_up_to();
+            let end = error.error_len().map_or(input.len(), |len| start + len);
+            match invalid.last_mut() {
+                Some(last) if last.range.end == start => last.range.end = end,
+                _ => invalid.push(InvalidUtf8 {
+                    range: start..end,
+                    line: lines.position(start).expect("offset within input").line(),
+                }),
+            }
+            offset = end;
+        }
+
+        (lines, invalid)
+    }
+
+    /// Parse a byte string that may not be valid UTF-8.
+    ///
+    /// Positions are byte offsets, as with [parse](Lines::parse), so columns count bytes rather
+    /// than characters.
+    ///
+    /// ```
+    /// use bstr::ByteSlice;
+    /// use line_position::Lines;
+    ///
+    /// let log = b"ok\nbad \xff byte\n".as_bstr();
+    /// let lines = Lines::parse_bstr(log);
+    /// assert_eq!(lines.position(9).unwrap().offset(), 6);
+    /// assert_eq!(lines.line_lossy(log, 2).unwrap(), "bad \u{FFFD} byte");
+    /// ```
+    #[cfg(feature = "bstr")]
+    pub fn parse_bstr(input: &BStr) -> Self {
+        Lines::parse_bytes(input)
+    }
+
+    /// Content of a one-indexed line of `input`, the byte string the index was built from,
+    /// without its terminator.
+    #[cfg(feature = "bstr")]
+    pub fn line_bstr<'a>(&self, input: &'a BStr, line: usize) -> Result<&'a BStr, LinesError> {
+        let line = line
+            .checked_sub(1)
+            .and_then(|index| self.line(index))
+            .ok_or(LinesError::LineOutOfBounds)?;
+        input
+            .get(line.start..line.content_end)
+            .map(ByteSlice::as_bstr)
+            .ok_or(LinesError::StaleIndex)
+    }
+
+    /// Content of a one-indexed line of `input` for display, with invalid UTF-8 replaced by
+    /// U+FFFD. Valid lines are borrowed without copying.
+    #[cfg(feature = "bstr")]
+    pub fn line_lossy<'a>(&self, input: &'a BStr, line: usize) -> Result<Cow<'a, str>, LinesError> {
+        Ok(self.line_bstr(input, line)?.to_str_lossy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_input_has_no_errors() {
+        let (lines, invalid) = Lines::parse_bytes_with_errors("abc\r\ndé\r\n".as_bytes());
+
+        assert_eq!(lines.num_lines(), 2, "number of lines is 2");
+        assert!(invalid.is_empty());
+    }
+
+    #[test]
+    fn parse_bytes() {
+        let input = b"\xc3\r\n\xc3\x28\r\n";
+        let lines = Lines::parse_bytes(input);
+
+        assert_eq!(lines.num_lines(), 2, "number of lines is 2");
+        assert_eq!(lines.line_span(2).ok(), Some(3..7));
+        assert_eq!(lines.line_content_span(1).ok(), Some(0..1));
+    }
+
+    #[test]
+    fn invalid_runs() {
+        let input = b"\xff\xfeabc\ndef\xc3\nghi\xe2\x82";
+        let (lines, invalid) = Lines::parse_bytes_with_errors(input);
+
+        assert_eq!(lines.num_lines(), 3, "number of lines is 3");
+        assert_eq!(
+            invalid,
+            vec![
+                InvalidUtf8 {
+                    range: 0..2,
+                    line: 1
+                },
+                InvalidUtf8 {
+                    range: 9..10,
+                    line: 2
+                },
+                InvalidUtf8 {
+                    range: 14..16,
+                    line: 3
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "bstr")]
+    #[test]
+    fn bstr_lines() -> Result<(), LinesError> {
+        let input = b"caf\xe9\r\nna\xefve\r\n".as_bstr();
+        let lines = Lines::parse_bstr(input);
+
+        assert_eq!(lines.num_lines(), 2);
+        assert_eq!(lines.line_bstr(input, 1)?, b"caf\xe9".as_bstr());
+        assert_eq!(lines.line_lossy(input, 2)?, "na\u{FFFD}ve");
+        assert!(matches!(
+            lines.line_lossy(input, 3),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.line_bstr(b"short".as_bstr(), 2),
+            Err(LinesError::StaleIndex)
+        ));
+
+        Ok(())
+    }
+}
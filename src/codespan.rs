@@ -0,0 +1,107 @@
+//! Rendering diagnostics with `codespan-reporting`.
+
+use core::ops::Range;
+
+use codespan_reporting::files::{Error, Files};
+
+use crate::workspace::{FileId, SourceFile, Workspace};
+
+/// Makes the workspace the file database of `codespan-reporting`, so diagnostics are rendered
+/// with the indexes it already holds.
+///
+/// Files are named by their paths, and line numbers start with 1 as elsewhere in this crate.
+/// Requires the `codespan-reporting` feature.
+///
+/// ```
+/// use codespan_reporting::diagnostic::{Diagnostic, Label};
+/// use codespan_reporting::term::{self, Config};
+/// use line_position::workspace::Workspace;
+///
+/// let mut workspace = Workspace::new();
+/// let id = workspace.add_file("src/main.rs", "fn main() {\n    oops\n}\n");
+/// let diagnostic = Diagnostic::error()
+///     .with_message("unknown name")
+///     .with_label(Label::primary(id, 16..20));
+/// let report = term::emit_into_string(&Config::default(), &workspace, &diagnostic).unwrap();
+/// assert!(report.contains("src/main.rs:2:5"));
+/// ```
+impl<'a> Files<'a> for Workspace {
+    type FileId = FileId;
+    type Name = String;
+    type Source = &'a str;
+
+    fn name(&'a self, id: FileId) -> Result<String, Error> {
+        let path = self.path(id).ok_or(Error::FileMissing)?;
+        Ok(path.display().to_string())
+    }
+
+    fn source(&'a self, id: FileId) -> Result<&'a str, Error> {
+        Ok(file(self, id)?.text())
+    }
+
+    fn line_index(&'a self, id: FileId, byte_index: usize) -> Result<usize, Error> {
+        let lines = file(self, id)?.lines();
+        Ok(lines.position_clamped(byte_index).line() - 1)
+    }
+
+    fn line_range(&'a self, id: FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        let lines = file(self, id)?.lines();
+        // Past a final terminator, or in an empty file, there is one more, empty line.
+        let last = match lines.has_trailing_newline() || lines.num_lines() == 0 {
+            true => lines.num_lines(),
+            false => lines.num_lines() - 1,
+        };
+        match lines.line_span(line_index + 1) {
+            Ok(span) => Ok(span),
+            Err(_) if line_index == last => Ok(lines.total_len()..lines.total_len()),
+            Err(_) => Err(Error::LineTooLarge {
+                given: line_index,
+                max: last,
+            }),
+        }
+    }
+}
+
+/// Look up a file of the workspace, failing as `codespan-reporting` expects.
+fn file(workspace: &Workspace, id: FileId) -> Result<&SourceFile, Error> {
+    workspace.get_by_id(id).ok_or(Error::FileMissing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan_reporting::files::SimpleFile;
+
+    #[test]
+    fn matches_simple_file() -> Result<(), Error> {
+        for text in ["", "a", "ab\ncd", "ab\r\n\r\ncé\n"] {
+            let mut workspace = Workspace::new();
+            let id = workspace.add_file("a.txt", text);
+            let simple = SimpleFile::new("a.txt", text);
+
+            assert_eq!(workspace.name(id)?, "a.txt");
+            assert_eq!(workspace.source(id)?, text);
+            for byte_index in 0..=text.len() + 1 {
+                assert_eq!(
+                    workspace.location(id, byte_index)?,
+                    simple.location((), byte_index)?,
+                    "{text:?} at {byte_index}"
+                );
+            }
+            for line_index in 0..4 {
+                assert_eq!(
+                    workspace.line_range(id, line_index).ok(),
+                    simple.line_range((), line_index).ok(),
+                    "{text:?} line {line_index}"
+                );
+            }
+        }
+
+        let workspace = Workspace::new();
+        let mut other = Workspace::new();
+        let id = other.add_file("b.txt", "b");
+        assert!(matches!(workspace.source(id), Err(Error::FileMissing)));
+
+        Ok(())
+    }
+}
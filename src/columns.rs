@@ -0,0 +1,246 @@
+//! Character and UTF-16 columns that stay cheap on extremely long lines.
+//!
+//! Converting a byte offset into a character or UTF-16 column means scanning the line up to the
+//! offset, which is slow for minified files whose single line can be megabytes long. A
+//! [ColumnIndex] records checkpoints every few kilobytes so conversions only scan from the
+//! nearest checkpoint.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{Lines, LinesError};
+
+/// Default distance in bytes between checkpoints.
+const DEFAULT_INTERVAL: usize = 4096;
+
+/// Checkpoint index for character and UTF-16 column conversions.
+///
+/// The index is built from the same text as a [Lines] index and is used alongside it. Every
+/// conversion scans at most one checkpoint interval of text, regardless of line length.
+///
+/// ```
+/// use line_position::{columns::ColumnIndex, Lines};
+///
+/// let source = "ab\n😀é = 1;\n";
+/// let lines = Lines::parse(source);
+/// let columns = ColumnIndex::new(source);
+/// // The "=" at byte offset 10 follows an emoji (two UTF-16 units), "é" and a space.
+/// assert_eq!(columns.char_column(&lines, source, 10).unwrap(), 3);
+/// assert_eq!(columns.utf16_column(&lines, source, 10).unwrap(), 4);
+/// assert_eq!(columns.offset_of_utf16(&lines, source, 2, 4).unwrap(), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ColumnIndex {
+    checkpoints: Vec<Checkpoint>,
+}
+
+/// Counts of characters and UTF-16 code units preceding a character boundary.
+#[derive(Debug, Clone, Copy)]
+struct Checkpoint {
+    byte: usize,
+    chars: usize,
+    utf16: usize,
+}
+
+impl ColumnIndex {
+    /// Build an index with a checkpoint about every 4 KiB of `source`.
+    pub fn new(source: &str) -> Self {
+        Self::with_interval(source, DEFAULT_INTERVAL)
+    }
+
+    /// Build an index with a checkpoint about every `interval` bytes of `source`.
+    ///
+    /// Smaller intervals make conversions faster at the cost of memory.
+    pub fn with_interval(source: &str, interval: usize) -> Self {
+        let interval = interval.max(1);
+        let mut checkpoints = vec![Checkpoint {
+            byte: 0,
+            chars: 0,
+            utf16: 0,
+        }];
+        let mut counts = checkpoints[0];
+        for (byte, c) in source.char_indices() {
+            if byte >= counts.byte + interval {
+                counts.byte = byte;
+                checkpoints.push(counts);
+            }
+            counts.chars += 1;
+            counts.utf16 += c.len_utf16();
+        }
+        ColumnIndex { checkpoints }
+    }
+
+    /// Zero-indexed column of a byte offset within its line, counted in characters.
+    ///
+    /// An offset inside a multi-byte character is treated as the start of that character.
+    pub fn char_column(
+        &self,
+        lines: &Lines,
+        source: &str,
+        offset: usize,
+    ) -> Result<usize, LinesError> {
+        let (start, offset) = column_range(lines, offset)?;
+        Ok(self.counts(source, offset).chars - self.counts(source, start).chars)
+    }
+
+    /// Zero-indexed column of a byte offset within its line, counted in UTF-16 code units.
+    ///
+    /// An offset inside a multi-byte character is treated as the start of that character.
+    pub fn utf16_column(
+        &self,
+        lines: &Lines,
+        source: &str,
+        offset: usize,
+    ) -> Result<usize, LinesError> {
+        let (start, offset) = column_range(lines, offset)?;
+        Ok(self.counts(source, offset).utf16 - self.counts(source, start).utf16)
+    }
+
+    /// Byte offset of a zero-indexed UTF-16 column on a one-indexed line.
+    ///
+    /// A column in the middle of a surrogate pair maps to the start of that character. Fails
+    /// with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist, or
+    /// [ColumnOutOfBounds](LinesError::ColumnOutOfBounds) if the column is past the end of the
+    /// line content.
+    pub fn offset_of_utf16(
+        &self,
+        lines: &Lines,
+        source: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<usize, LinesError> {
+        let index = line.checked_sub(1).ok_or(LinesError::LineOutOfBounds)?;
+        let line = lines.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        let start = lines.column_start(index);
+        let line_counts = self.counts(source, start);
+        let target = line_counts.utf16 + column;
+
+        let nearest = self.checkpoints[self
+            .checkpoints
+            .partition_point(|checkpoint| checkpoint.utf16 <= target)
+            - 1];
+        if nearest.byte > line.content_end {
+            return Err(LinesError::ColumnOutOfBounds);
+        }
+        let mut counts = match nearest.byte > start {
+            true => nearest,
+            false => line_counts,
+        };
+        for (byte, c) in source[counts.byte..line.content_end].char_indices() {
+            if counts.utf16 + c.len_utf16() > target {
+                return Ok(counts.byte + byte);
+            }
+            counts.utf16 += c.len_utf16();
+        }
+        match counts.utf16 == target {
+            true => Ok(line.content_end),
+            false => Err(LinesError::ColumnOutOfBounds),
+        }
+    }
+
+    /// Counts preceding a byte offset, scanning from the nearest checkpoint.
+    fn counts(&self, source: &str, offset: usize) -> Checkpoint {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let nearest = self
+            .checkpoints
+            .partition_point(|checkpoint| checkpoint.byte <= offset)
+            - 1;
+        let mut counts = self.checkpoints[nearest];
+        for c in source[counts.byte..offset].chars() {
+            counts.chars += 1;
+            counts.utf16 += c.len_utf16();
+        }
+        counts.byte = offset;
+        counts
+    }
+}
+
+/// Offset from which columns are counted on the line of `offset`, and the offset moved out of a
+/// skipped byte order mark, which is at column 0.
+fn column_range(lines: &Lines, offset: usize) -> Result<(usize, usize), LinesError> {
+    let start = lines.column_start(lines.position(offset)?.line() - 1);
+    Ok((start, offset.max(start)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_line_matches_scanning() -> Result<(), LinesError> {
+        let source = format!("x\n{}\n", "aé😀".repeat(500));
+        let lines = Lines::parse(&source);
+        let columns = ColumnIndex::with_interval(&source, 64);
+
+        for (offset, _) in source.char_indices().skip(2).step_by(7) {
+            let prefix = &source[2..offset];
+            assert_eq!(
+                columns.char_column(&lines, &source, offset)?,
+                prefix.chars().count()
+            );
+            let utf16 = prefix.encode_utf16().count();
+            assert_eq!(columns.utf16_column(&lines, &source, offset)?, utf16);
+            assert_eq!(columns.offset_of_utf16(&lines, &source, 2, utf16)?, offset);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn reverse_lookup_bounds() -> Result<(), LinesError> {
+        let source = "😀\nab";
+        let lines = Lines::parse(source);
+        let columns = ColumnIndex::new(source);
+
+        assert_eq!(
+            columns.offset_of_utf16(&lines, source, 1, 1)?,
+            0,
+            "inside surrogate pair"
+        );
+        assert_eq!(
+            columns.offset_of_utf16(&lines, source, 1, 2)?,
+            4,
+            "end of line"
+        );
+        assert!(columns.offset_of_utf16(&lines, source, 1, 3).is_err());
+        assert_eq!(columns.offset_of_utf16(&lines, source, 2, 2)?, 7);
+        assert!(columns.offset_of_utf16(&lines, source, 3, 0).is_err());
+
+        let source = "a\n".repeat(100);
+        let lines = Lines::parse(&source);
+        let columns = ColumnIndex::with_interval(&source, 8);
+        assert!(matches!(
+            columns.offset_of_utf16(&lines, &source, 1, 50),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn skip_bom_round_trip() -> Result<(), LinesError> {
+        let source = "\u{FEFF}a😀b\nc";
+        let lines = crate::ParseOptions::new().skip_bom(true).parse(source)?;
+        let columns = ColumnIndex::new(source);
+
+        assert_eq!(
+            columns.utf16_column(&lines, source, 1)?,
+            0,
+            "inside the BOM"
+        );
+        assert_eq!(columns.char_column(&lines, source, 1)?, 0, "inside the BOM");
+        for (offset, column) in [(3, 0), (4, 1), (8, 3), (9, 4)] {
+            assert_eq!(columns.utf16_column(&lines, source, offset)?, column);
+            assert_eq!(
+                columns.utf16_column(&lines, source, offset)?,
+                lines.position_utf16(source, offset)?.offset()
+            );
+            assert_eq!(columns.offset_of_utf16(&lines, source, 1, column)?, offset);
+        }
+        assert!(columns.offset_of_utf16(&lines, source, 1, 5).is_err());
+
+        Ok(())
+    }
+}
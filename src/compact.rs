@@ -0,0 +1,201 @@
+//! A smaller index with 32-bit offsets, for keeping many files indexed at once.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{LineEnding, LineIndex, LinePosition, Lines, LinesError, LinesResult, BOM};
+
+/// A line index like [Lines] that stores offsets as `u32`, taking 5 bytes per line instead of
+/// 9 on 64-bit targets.
+///
+/// Input is limited to `u32::MAX` bytes, just under 4 GiB. Lookups behave as those of the
+/// [Lines] index it was built from, which is available through [LineIndex].
+///
+/// ```
+/// use line_position::compact::CompactLines;
+/// use line_position::LineIndex;
+///
+/// let lines = CompactLines::parse("abc\r\ndef\n").unwrap();
+/// assert_eq!(lines.num_lines(), 2);
+/// assert_eq!(lines.position(6).unwrap().offset(), 1);
+/// assert_eq!(lines.line_content_span(1).unwrap(), 0..3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CompactLines {
+    /// Offset of the first byte of each line, as in [Lines].
+    starts: Vec<u32>,
+    /// Terminator of each line, one shorter than `starts` if the last line has none.
+    endings: Vec<LineEnding>,
+    len: u32,
+    /// Whether columns on line 1 are counted from after a byte order mark.
+    skip_bom: bool,
+}
+
+impl CompactLines {
+    /// Parse input like [Lines::parse].
+    ///
+    /// Fails with [InputTooLong](LinesError::InputTooLong) if the input is longer than
+    /// `u32::MAX` bytes.
+    pub fn parse(input: &str) -> Result<Self, LinesError> {
+        check_len(input.len())?;
+        CompactLines::try_from(&Lines::parse(input))
+    }
+
+    /// Byte range of a line, starting with 1, without its terminator.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist.
+    pub fn line_content_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+        let index = self.index(line)?;
+        Ok(self.start(index)..self.content_end(index))
+    }
+
+    /// Terminator of a line, starting with 1, as in [Lines::line_ending_of].
+    pub fn line_ending_of(&self, line: usize) -> Result<Option<LineEnding>, LinesError> {
+        Ok(self.endings.get(self.index(line)?).copied())
+    }
+
+    /// Zero-indexed line of a line number, starting with 1.
+    fn index(&self, line: usize) -> Result<usize, LinesError> {
+        line.checked_sub(1)
+            .filter(|&index| index < self.starts.len())
+            .ok_or(LinesError::LineOutOfBounds)
+    }
+
+    fn start(&self, index: usize) -> usize {
+        self.starts[index] as usize
+    }
+
+    fn end(&self, index: usize) -> usize {
+        self.starts.get(index + 1).copied().unwrap_or(self.len) as usize
+    }
+
+    fn content_end(&self, index: usize) -> usize {
+        self.end(index) - self.endings.get(index).map_or(0, |ending| ending.len())
+    }
+
+    /// Offset from which columns are counted on a zero-indexed line, as in [Lines].
+    fn column_start(&self, index: usize) -> usize {
+        match index == 0 && self.skip_bom {
+            true => BOM.len(),
+            false => self.start(index),
+        }
+    }
+}
+
+/// Fail with [InputTooLong](LinesError::InputTooLong) for input that does not fit `u32` offsets.
+fn check_len(len: usize) -> Result<u32, LinesError> {
+    u32::try_from(len).map_err(|_| LinesError::InputTooLong {
+        len,
+        max: u32::MAX as usize,
+    })
+}
+
+/// Convert an index, failing with [InputTooLong](LinesError::InputTooLong) if it covers more than
+/// `u32::MAX` bytes.
+impl TryFrom<&Lines> for CompactLines {
+    type Error = LinesError;
+
+    fn try_from(lines: &Lines) -> Result<Self, LinesError> {
+        let len = check_len(lines.total_len())?;
+        Ok(CompactLines {
+            // Every offset is at most the length, so the conversions cannot truncate.
+            starts: lines
+                .line_starts()
+                .iter()
+                .map(|&start| start as u32)
+                .collect(),
+            endings: lines.lines().filter_map(|line| line.ending).collect(),
+            len,
+            skip_bom: lines.bom && lines.skip_bom,
+        })
+    }
+}
+
+impl LineIndex for CompactLines {
+    fn position(&self, offset: usize) -> LinesResult {
+        let index = self
+            .starts
+            .partition_point(|&start| start as usize <= offset)
+            .saturating_sub(1);
+        match offset < self.len as usize {
+            true => Ok(LinePosition::new(
+                index + 1,
+                offset.saturating_sub(self.column_start(index)),
+            )),
+            false => Err(LinesError::OffsetOutOfBounds {
+                offset,
+                max: self.len as usize,
+            }),
+        }
+    }
+
+    fn offset_of(&self, line: usize, column: usize) -> Result<usize, LinesError> {
+        let index = self.index(line)?;
+        let start = self.column_start(index);
+        match column <= self.content_end(index).saturating_sub(start) {
+            true => Ok(start + column),
+            false => Err(LinesError::ColumnOutOfBounds),
+        }
+    }
+
+    fn num_lines(&self) -> usize {
+        self.starts.len()
+    }
+
+    fn line_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+        let index = self.index(line)?;
+        Ok(self.start(index)..self.end(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    #[test]
+    fn matches_lines() -> Result<(), LinesError> {
+        let input = "\u{FEFF}ab\r\n\ncd\u{2028}e";
+        let options = ParseOptions::new().skip_bom(true).unicode_breaks(true);
+        for lines in [Lines::parse(input), options.parse(input)?, Lines::parse("")] {
+            let compact = CompactLines::try_from(&lines)?;
+
+            assert_eq!(compact.num_lines(), lines.num_lines());
+            for offset in 0..=input.len() + 1 {
+                assert_eq!(
+                    compact.position(offset).ok(),
+                    lines.position(offset).ok(),
+                    "offset {offset}"
+                );
+            }
+            for line in 0..=lines.num_lines() + 1 {
+                assert_eq!(compact.line_span(line).ok(), lines.line_span(line).ok());
+                assert_eq!(
+                    compact.line_content_span(line).ok(),
+                    lines.line_content_span(line).ok()
+                );
+                assert_eq!(
+                    compact.line_ending_of(line).ok(),
+                    lines.line_ending_of(line).ok()
+                );
+                for column in 0..6 {
+                    assert_eq!(
+                        compact.offset_of(line, column).ok(),
+                        lines.offset_of(line, column).ok()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn too_long() {
+        assert!(matches!(
+            check_len(u32::MAX as usize + 1),
+            Err(LinesError::InputTooLong { max, .. }) if max == u32::MAX as usize
+        ));
+        assert!(check_len(u32::MAX as usize).is_ok());
+    }
+}
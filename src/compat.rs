@@ -0,0 +1,432 @@
+//! Compatibility shims mirroring the APIs of the
+//! [line-numbers](https://crates.io/crates/line-numbers) and
+//! [line-span](https://crates.io/crates/line-span) crates, backed by [Lines].
+//!
+//! Requires the `compat` feature.
+//!
+//! Existing call sites can switch their imports to this module and migrate to [Lines] at their
+//! own pace. One difference remains: line breaks follow the parsing rules of [Lines::parse]
+//! rather than those of the original crates.
+//!
+//! ```
+//! use line_position::compat::LinePositions;
+//!
+//! let line_positions = LinePositions::from("foo\nbar\nbaz\n");
+//! let (line_num, column) = line_positions.from_offset(5);
+//! assert_eq!(line_num.display(), "2");
+//! assert_eq!(column, 1);
+//! ```
+
+// Mirrors the `from_*` method names of the original crate.
+#![allow(clippy::wrong_self_convention)]
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::{Deref, Range};
+
+use crate::{Lines, LinesError};
+
+/// A distinct number type for line numbers, zero-indexed internally.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineNumber(pub u32);
+
+impl LineNumber {
+    /// The one-indexed line number, for display.
+    pub fn display(self) -> String {
+        format!("{}", self.0 + 1)
+    }
+
+    /// The zero-indexed line number.
+    pub fn as_usize(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Debug for LineNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LineNumber: {} (zero-indexed: {})",
+            self.display(),
+            self.0
+        )
+    }
+}
+
+impl From<u32> for LineNumber {
+    fn from(number: u32) -> Self {
+        Self(number)
+    }
+}
+
+/// A range within a single line of a string.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, PartialOrd, Ord, Hash)]
+pub struct SingleLineSpan {
+    /// Line of the span.
+    pub line: LineNumber,
+    /// Start column.
+    pub start_col: u32,
+    /// End column.
+    pub end_col: u32,
+}
+
+/// Converter from absolute string positions to line-relative positions.
+#[derive(Debug)]
+pub struct LinePositions {
+    lines: Lines,
+}
+
+impl From<&str> for LinePositions {
+    fn from(s: &str) -> Self {
+        LinePositions {
+            lines: Lines::parse(s),
+        }
+    }
+}
+
+impl From<Lines> for LinePositions {
+    fn from(lines: Lines) -> Self {
+        LinePositions { lines }
+    }
+}
+
+impl LinePositions {
+    /// Offset from which columns are counted and offset of the terminating `\n` (or end of
+    /// input) of a zero-indexed line.
+    ///
+    /// As in the original crate, input ending with a line terminator has a final empty line.
+    /// Columns on line 1 start after a [skipped](crate::ParseOptions::skip_bom) byte order mark.
+    fn bounds(&self, index: usize) -> (usize, usize) {
+        let start = self.lines.column_start(index);
+        match self.lines.line(index) {
+            Some(line) if line.content_end < line.end => (start, line.end - 1),
+            Some(line) => (start, line.end),
+            None => (self.lines.total_len(), self.lines.total_len()),
+        }
+    }
+
+    /// Return the line and column corresponding to this `offset`, measured in bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is out of bounds.
+    pub fn from_offset(&self, offset: usize) -> (LineNumber, usize) {
+        let len = self.lines.total_len();
+        assert!(
+            offset <= len,
+            "Offset {} is out of bounds for a string of length {}",
+            offset,
+            len
+        );
+
+        let index = match self.lines.position(offset) {
+            Ok(position) => position.line() - 1,
+            Err(_) => match self.bounds(self.lines.num_lines().saturating_sub(1)) {
+                (_, end) if end == len && self.lines.num_lines() > 0 => self.lines.num_lines() - 1,
+                _ => self.lines.num_lines(),
+            },
+        };
+        let (start, _) = self.bounds(index);
+
+        (LineNumber::from(index as u32), offset.saturating_sub(start))
+    }
+
+    /// Convert this region into line spans. If the region includes a newline, the vec will
+    /// contain multiple items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region_start` or `region_end` are out of bounds, or if `region_start` is
+    /// greater than `region_end`.
+    pub fn from_region(&self, region_start: usize, region_end: usize) -> Vec<SingleLineSpan> {
+        assert!(region_start <= region_end);
+
+        let (first_line, _) = self.from_offset(region_start);
+        let (last_line, _) = self.from_offset(region_end);
+
+        (first_line.0..=last_line.0)
+            .map(|index| {
+                let (line_start, line_end) = self.bounds(index as usize);
+                SingleLineSpan {
+                    line: index.into(),
+                    start_col: region_start.saturating_sub(line_start) as u32,
+                    end_col: region_end.min(line_end).saturating_sub(line_start) as u32,
+                }
+            })
+            .collect()
+    }
+
+    /// Given a region in the current LinePositions, convert it to be relative to a `start`
+    /// offset in a larger, enclosing string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `region_start` or `region_end` are out of bounds, or if `region_start` is
+    /// greater than `region_end`.
+    pub fn from_region_relative_to(
+        &self,
+        start: SingleLineSpan,
+        region_start: usize,
+        region_end: usize,
+    ) -> Vec<SingleLineSpan> {
+        self.from_region(region_start, region_end)
+            .into_iter()
+            .map(|span| SingleLineSpan {
+                line: (span.line.0 + start.line.0).into(),
+                // Only the first line shares its start with the enclosing string.
+                start_col: span.start_col + (span.line.0 == 0) as u32 * start.start_col,
+                end_col: span.end_col + (span.line.0 == 0) as u32 * start.start_col,
+            })
+            .collect()
+    }
+}
+
+/// A line of text with its byte range, mirroring `line_span::LineSpan`.
+///
+/// Unlike the original, spans come from a precomputed index rather than by scanning the text.
+///
+/// ```
+/// use line_position::{compat, Lines};
+///
+/// let text = "foo\r\nbar\r\nbaz";
+/// let lines = Lines::parse(text);
+/// let span = compat::find_line_span(&lines, text, 6).unwrap();
+/// assert_eq!(span.as_str(), "bar");
+/// assert_eq!(span.range(), 5..8);
+/// assert_eq!(span.range_with_ending(), 5..10);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LineSpan<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+    ending: usize,
+}
+
+impl<'a> LineSpan<'a> {
+    /// Returns the byte index of the start of the line.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the byte index of the end of the line, excluding the line ending.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the byte index of the end of the line, including the line ending.
+    pub fn ending(&self) -> usize {
+        self.ending
+    }
+
+    /// Returns the byte range of the line, excluding the line ending.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the byte range of the line, including the line ending.
+    pub fn range_with_ending(&self) -> Range<usize> {
+        self.start..self.ending
+    }
+
+    /// Returns the byte range of the line ending alone.
+    pub fn ending_range(&self) -> Range<usize> {
+        self.end..self.ending
+    }
+
+    /// Returns the text of the line, excluding the line ending.
+    pub fn as_str(&self) -> &'a str {
+        &self.text[self.range()]
+    }
+
+    /// Returns the text of the line, including the line ending.
+    pub fn as_str_with_ending(&self) -> &'a str {
+        &self.text[self.range_with_ending()]
+    }
+
+    /// Returns the line ending alone.
+    pub fn ending_str(&self) -> &'a str {
+        &self.text[self.ending_range()]
+    }
+}
+
+impl Deref for LineSpan<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a> From<LineSpan<'a>> for &'a str {
+    fn from(span: LineSpan<'a>) -> &'a str {
+        span.as_str()
+    }
+}
+
+impl From<LineSpan<'_>> for Range<usize> {
+    fn from(span: LineSpan<'_>) -> Range<usize> {
+        span.range()
+    }
+}
+
+impl fmt::Display for LineSpan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// Iterator over the [LineSpan]s of an index, created by [line_spans].
+#[derive(Debug, Clone)]
+pub struct LineSpanIter<'a> {
+    text: &'a str,
+    lines: &'a Lines,
+    /// Zero-indexed lines not yet yielded from either end.
+    remaining: Range<usize>,
+}
+
+impl<'a> Iterator for LineSpanIter<'a> {
+    type Item = LineSpan<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.remaining.next()?;
+        line_span(self.lines, self.text, index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for LineSpanIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let index = self.remaining.next_back()?;
+        line_span(self.lines, self.text, index)
+    }
+}
+
+impl ExactSizeIterator for LineSpanIter<'_> {}
+
+impl FusedIterator for LineSpanIter<'_> {}
+
+/// Span of a zero-indexed line, if present.
+fn line_span<'a>(lines: &Lines, text: &'a str, index: usize) -> Option<LineSpan<'a>> {
+    let line = lines.line(index)?;
+    Some(LineSpan {
+        text,
+        start: line.start,
+        end: line.content_end,
+        ending: line.end,
+    })
+}
+
+/// Iterate over the spans of all lines in `text`, which must be the text `lines` was built from.
+///
+/// This mirrors `line_span::LineSpans::line_spans`.
+pub fn line_spans<'a>(lines: &'a Lines, text: &'a str) -> LineSpanIter<'a> {
+    LineSpanIter {
+        text,
+        lines,
+        remaining: 0..lines.num_lines(),
+    }
+}
+
+/// Find the span of the line containing the byte offset `index`, mirroring
+/// `line_span::find_line_range` but returning the full [LineSpan].
+///
+/// `text` must be the text `lines` was built from.
+pub fn find_line_span<'a>(
+    lines: &Lines,
+    text: &'a str,
+    index: usize,
+) -> Result<LineSpan<'a>, LinesError> {
+    let position = lines.position(index)?;
+    Ok(line_span(lines, text, position.line() - 1).expect("position refers to a parsed line"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_offset() {
+        let positions = LinePositions::from("foo\nbar\n");
+
+        assert_eq!(positions.from_offset(0), (LineNumber(0), 0));
+        assert_eq!(positions.from_offset(3), (LineNumber(0), 3), "newline");
+        assert_eq!(positions.from_offset(5), (LineNumber(1), 1));
+        assert_eq!(positions.from_offset(8), (LineNumber(2), 0), "final line");
+
+        let positions = LinePositions::from("foo\nbar");
+        assert_eq!(positions.from_offset(7), (LineNumber(1), 3), "end of input");
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_offset_out_of_bounds() {
+        LinePositions::from("foo").from_offset(4);
+    }
+
+    #[test]
+    fn line_spans_of_index() {
+        let text = "foo\nbar\r\nbaz";
+        let lines = Lines::parse(text);
+        let spans: Vec<_> = line_spans(&lines, text).collect();
+
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].ending_str(), "\n");
+        assert_eq!(spans[1].as_str(), "bar");
+        assert_eq!(spans[1].ending_str(), "\r\n");
+        assert_eq!(spans[2].range(), 9..12);
+        assert_eq!(spans[2].range_with_ending(), 9..12);
+        assert_eq!(&*spans[2], "baz");
+        assert_eq!(line_spans(&lines, text).len(), 3, "exact size");
+        assert_eq!(line_spans(&lines, text).next_back(), Some(spans[2]));
+        assert!(find_line_span(&lines, text, 12).is_err(), "out of bounds");
+    }
+
+    #[test]
+    fn from_region() {
+        let positions = LinePositions::from("foo\nbar\nbaz");
+
+        assert_eq!(
+            positions.from_region(1, 6),
+            vec![
+                SingleLineSpan {
+                    line: 0.into(),
+                    start_col: 1,
+                    end_col: 3
+                },
+                SingleLineSpan {
+                    line: 1.into(),
+                    start_col: 0,
+                    end_col: 2
+                },
+            ]
+        );
+
+        let start = SingleLineSpan {
+            line: 4.into(),
+            start_col: 10,
+            end_col: 20,
+        };
+        assert_eq!(
+            positions.from_region_relative_to(start, 5, 9),
+            vec![
+                SingleLineSpan {
+                    line: 5.into(),
+                    start_col: 1,
+                    end_col: 3
+                },
+                SingleLineSpan {
+                    line: 6.into(),
+                    start_col: 0,
+                    end_col: 1
+                },
+            ]
+        );
+    }
+}
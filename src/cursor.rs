@@ -0,0 +1,153 @@
+//! Lookups that remember the last line they found, for offsets that arrive roughly in order.
+
+use alloc::vec::Vec;
+
+use crate::{LinePosition, Lines, LinesResult};
+
+/// Cursor over a [Lines] index that checks the line of the previous lookup and the one after it
+/// before searching, created by [cursor](Lines::cursor).
+///
+/// Diagnostics and tokens usually arrive sorted, so most lookups then avoid the binary search.
+/// The cursor borrows the index, which stays shareable across threads; give each thread its own.
+///
+/// ```
+/// use line_position::Lines;
+///
+/// let lines = Lines::parse("abc\ndef\nghi\n");
+/// let mut cursor = lines.cursor();
+/// assert_eq!(cursor.position(5).unwrap(), lines.position(5).unwrap());
+/// assert_eq!(cursor.position(9).unwrap().line(), 3);
+/// assert!(cursor.position(12).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    lines: &'a Lines,
+    /// Zero-indexed line of the last successful lookup.
+    index: usize,
+}
+
+impl Cursor<'_> {
+    /// Lookup the position of an offset, with the same result as [Lines::position].
+    pub fn position(&mut self, offset: usize) -> LinesResult {
+        let index = match (self.index..self.index + 2).find(|&index| self.contains(index, offset)) {
+            Some(index) => index,
+            None => self.lines.line_of(offset)? - 1,
+        };
+        self.index = index;
+        Ok(LinePosition::new(
+            index + 1,
+            offset.saturating_sub(self.lines.column_start(index)),
+        ))
+    }
+
+    /// Whether a zero-indexed line contains an offset.
+    fn contains(&self, index: usize, offset: usize) -> bool {
+        self.lines
+            .line(index)
+            .is_some_and(|line| line.start <= offset && offset < line.end)
+    }
+}
+
+impl Lines {
+    /// Create a [Cursor] for looking up offsets that arrive roughly in order.
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            lines: self,
+            index: 0,
+        }
+    }
+
+    /// Look up the positions of many offsets, returning the results in the same order as
+    /// `offsets`.
+    ///
+    /// Each result is the same as that of [position](Lines::position) for the offset. Sorted
+    /// offsets, such as those of the tokens of a file, are resolved in a single sweep over the
+    /// lines instead of a search each.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("ab\ncd\n");
+    /// let positions = lines.positions(&[0, 4, 9]);
+    /// assert_eq!(positions[1].as_ref().unwrap().line(), 2);
+    /// assert!(positions[2].is_err());
+    /// ```
+    pub fn positions(&self, offsets: &[usize]) -> Vec<LinesResult> {
+        match offsets.windows(2).all(|pair| pair[0] <= pair[1]) {
+            true => {
+                let starts = self.line_starts();
+                let mut index = 0;
+                offsets
+                    .iter()
+                    .map(|&offset| {
+                        if offset >= self.total_len() {
+                            return self.position(offset);
+                        }
+                        while starts.get(index + 1).is_some_and(|&next| next <= offset) {
+                            index += 1;
+                        }
+                        Ok(LinePosition::new(
+                            index + 1,
+                            offset.saturating_sub(self.column_start(index)),
+                        ))
+                    })
+                    .collect()
+            }
+            false => offsets
+                .iter()
+                .map(|&offset| self.position(offset))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    #[test]
+    fn matches_position() {
+        let input = "\u{FEFF}ab\r\n\ncd\nefg";
+        let skipped = ParseOptions::new().skip_bom(true).parse(input).unwrap();
+        for lines in [Lines::parse(input), skipped, Lines::parse("")] {
+            let mut cursor = lines.cursor();
+            let forward = 0..input.len() + 2;
+            let jumps = [9, 0, 12, 4, 100, 5];
+            for offset in forward.clone().chain(forward.rev()).chain(jumps) {
+                assert_eq!(
+                    cursor.position(offset).ok(),
+                    lines.position(offset).ok(),
+                    "offset {offset}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn positions() {
+        let input = "\u{FEFF}ab\r\n\n\ncd\nefg";
+        let skipped = ParseOptions::new().skip_bom(true).parse(input).unwrap();
+        let sorted: Vec<usize> = (0..input.len() + 2).collect();
+        let unsorted = [9, 0, 12, 4, 100, 5];
+        for lines in [Lines::parse(input), skipped, Lines::parse("")] {
+            for offsets in [&sorted[..], &sorted[6..9], &unsorted, &[]] {
+                let expected: Vec<_> = offsets
+                    .iter()
+                    .map(|&offset| lines.position(offset))
+                    .collect();
+                assert_eq!(
+                    format!("{:?}", lines.positions(offsets)),
+                    format!("{expected:?}")
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn index_stays_shareable() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Lines>();
+        assert_sync::<Cursor<'_>>();
+    }
+}
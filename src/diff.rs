@@ -0,0 +1,161 @@
+//! Line-level differences between two texts.
+
+use std::ops::Range;
+
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use crate::{Lines, LinesError};
+
+/// Kind of a [LineChange].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    /// Lines only present in the new text.
+    Inserted,
+    /// Lines only present in the old text.
+    Deleted,
+    /// Lines of the old text replaced by different lines in the new text.
+    Changed,
+}
+
+/// A run of lines that differs between two texts.
+///
+/// Both ranges hold one-indexed line numbers and are half-open. The range on the side without
+/// lines is empty and sits where the lines were inserted or deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LineChange {
+    kind: ChangeKind,
+    old: Range<usize>,
+    new: Range<usize>,
+}
+
+impl LineChange {
+    /// Kind of change.
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    /// Affected line numbers of the old text.
+    pub fn old_lines(&self) -> Range<usize> {
+        self.old.clone()
+    }
+
+    /// Affected line numbers of the new text.
+    pub fn new_lines(&self) -> Range<usize> {
+        self.new.clone()
+    }
+}
+
+impl Lines {
+    /// Compare the lines of `old_text`, indexed by `self`, with those of `new_text`, indexed by
+    /// `new_lines`, using the patience diff algorithm.
+    ///
+    /// Lines compare including their terminators, so a changed line ending counts as a change.
+    /// Fails with [StaleIndex](LinesError::StaleIndex) if either text does not match its index.
+    ///
+    /// ```
+    /// use line_position::{diff::ChangeKind, Lines};
+    ///
+    /// let (old, new) = ("a\nb\nc\n", "a\nB\nc\nd\n");
+    /// let changes = Lines::parse(old).diff_lines(old, &Lines::parse(new), new).unwrap();
+    /// assert_eq!(changes.len(), 2);
+    /// assert_eq!((changes[0].kind(), changes[0].old_lines(), changes[0].new_lines()), (ChangeKind::Changed, 2..3, 2..3));
+    /// assert_eq!((changes[1].kind(), changes[1].old_lines(), changes[1].new_lines()), (ChangeKind::Inserted, 4..4, 4..5));
+    /// ```
+    pub fn diff_lines(
+        &self,
+        old_text: &str,
+        new_lines: &Lines,
+        new_text: &str,
+    ) -> Result<Vec<LineChange>, LinesError> {
+        let old = line_texts(self, old_text)?;
+        let new = line_texts(new_lines, new_text)?;
+
+        let changes = capture_diff_slices(Algorithm::Patience, &old, &new)
+            .into_iter()
+            .filter_map(|op| {
+                let (kind, old_index, old_len, new_index, new_len) = match op {
+                    DiffOp::Equal { .. } => return None,
+                    DiffOp::Delete {
+                        old_index,
+                        old_len,
+                        new_index,
+                    } => (ChangeKind::Deleted, old_index, old_len, new_index, 0),
+                    DiffOp::Insert {
+                        old_index,
+                        new_index,
+                        new_len,
+                    } => (ChangeKind::Inserted, old_index, 0, new_index, new_len),
+                    DiffOp::Replace {
+                        old_index,
+                        old_len,
+                        new_index,
+                        new_len,
+                    } => (ChangeKind::Changed, old_index, old_len, new_index, new_len),
+                };
+                Some(LineChange {
+                    kind,
+                    old: old_index + 1..old_index + old_len + 1,
+                    new: new_index + 1..new_index + new_len + 1,
+                })
+            })
+            .collect();
+        Ok(changes)
+    }
+}
+
+/// Text of every line, including its terminator, checking that `text` matches the index.
+fn line_texts<'a>(lines: &'a Lines, text: &'a str) -> Result<Vec<&'a str>, LinesError> {
+    Ok(lines
+        .lines_with_text(text)?
+        .map(|(_, _, line)| line)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> Vec<(ChangeKind, Range<usize>, Range<usize>)> {
+        Lines::parse(old)
+            .diff_lines(old, &Lines::parse(new), new)
+            .unwrap()
+            .into_iter()
+            .map(|change| (change.kind(), change.old_lines(), change.new_lines()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_lines() {
+        assert_eq!(diff("a\nb\n", "a\nb\n"), vec![]);
+        assert_eq!(
+            diff("a\nb\nc\n", "a\nc\n"),
+            vec![(ChangeKind::Deleted, 2..3, 2..2)]
+        );
+        assert_eq!(
+            diff("a\nc\n", "x\na\nb\nc\n"),
+            vec![
+                (ChangeKind::Inserted, 1..1, 1..2),
+                (ChangeKind::Inserted, 2..2, 3..4)
+            ]
+        );
+        assert_eq!(
+            diff("a\nb\nc", "a\nb\nc\n"),
+            vec![(ChangeKind::Changed, 3..4, 3..4)],
+            "added trailing newline"
+        );
+        assert_eq!(diff("", "a\n"), vec![(ChangeKind::Inserted, 1..1, 1..2)]);
+    }
+
+    #[test]
+    fn stale_text() {
+        let (lines, text) = (Lines::parse("abc\ndef\n"), "abc\ndef\n");
+        assert!(matches!(
+            lines.diff_lines("ab", &lines, text),
+            Err(LinesError::StaleIndex)
+        ));
+        assert!(matches!(
+            lines.diff_lines(text, &lines, "abcé\nde"),
+            Err(LinesError::StaleIndex)
+        ));
+    }
+}
@@ -0,0 +1,918 @@
+//! An editable text document that keeps its line index up to date.
+
+use std::ops::{Range, RangeInclusive};
+use std::sync::OnceLock;
+
+use crate::{check_range, Bias, LineEnding, Lines, LinesError, BOM};
+
+/// Text together with its [Lines] index, updated incrementally as the text is edited.
+///
+/// Edits only rescan the lines they touch. The document also carries the version number an
+/// editor assigns to each state of the text, which edits leave as it is.
+///
+/// ```
+/// use line_position::document::TextDocument;
+///
+/// let mut document = TextDocument::new("a\nb\nc\n");
+/// document.set_line(2, "bee").unwrap();
+/// document.delete_lines(3..=3).unwrap();
+/// assert_eq!(document.text(), "a\nbee\n");
+/// assert_eq!(document.lines().position(4).unwrap().line(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextDocument {
+    text: String,
+    lines: Lines,
+    version: i32,
+    /// Offsets and biases of anchors, indexed by [Anchor], or `None` once removed.
+    anchors: Vec<Option<(usize, Bias)>>,
+    counts: OnceLock<Counts>,
+}
+
+/// Handle to a position in a [TextDocument] that moves with edits, created by
+/// [anchor](TextDocument::anchor).
+///
+/// Text inserted or deleted before the anchor shifts it. Text inserted exactly at the anchor
+/// goes after it with [Backward](Bias::Backward) bias and before it with
+/// [Forward](Bias::Forward) bias, and an anchor inside replaced text moves to the start or end
+/// of the replacement accordingly.
+///
+/// ```
+/// use line_position::{document::TextDocument, Bias};
+///
+/// let mut document = TextDocument::new("ab");
+/// let before = document.anchor(1, Bias::Backward).unwrap();
+/// let after = document.anchor(1, Bias::Forward).unwrap();
+/// document.apply_edit(1..1, "xyz").unwrap();
+/// document.apply_edit(0..0, "_").unwrap();
+/// assert_eq!(document.anchor_offset(before), Some(2));
+/// assert_eq!(document.anchor_offset(after), Some(5));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Anchor(usize);
+
+/// Character and UTF-16 code unit counts of a document, computed on first use.
+#[derive(Debug, Clone)]
+struct Counts {
+    /// Counts of each line, including its terminator.
+    lines: Vec<(usize, usize)>,
+    chars: usize,
+    utf16: usize,
+}
+
+impl TextDocument {
+    /// Create a document, indexing its text.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let lines = Lines::parse(&text);
+        TextDocument {
+            text,
+            lines,
+            version: 0,
+            anchors: Vec::new(),
+            counts: OnceLock::new(),
+        }
+    }
+
+    /// Set the version of a new document, such as the one given by an LSP `didOpen`
+    /// notification.
+    pub fn with_version(mut self, version: i32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Version of the document, 0 unless set.
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Set the version of the document after editing it.
+    pub fn set_version(&mut self, version: i32) {
+        self.version = version;
+    }
+
+    /// Current text of the document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Index of the current text.
+    pub fn lines(&self) -> &Lines {
+        &self.lines
+    }
+
+    /// Text of a line, starting with 1, including its terminator.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist.
+    ///
+    /// ```
+    /// use line_position::document::TextDocument;
+    ///
+    /// let document = TextDocument::new("a\r\nb\r\n");
+    /// assert_eq!(document.line_text(2).unwrap(), "b\r\n");
+    /// assert_eq!(document.line_content(2).unwrap(), "b");
+    /// ```
+    pub fn line_text(&self, line: usize) -> Result<&str, LinesError> {
+        self.lines.text_of_lines(&self.text, line..=line)
+    }
+
+    /// Text of a line, starting with 1, without its terminator.
+    ///
+    /// Fails as [line_text](TextDocument::line_text) does.
+    pub fn line_content(&self, line: usize) -> Result<&str, LinesError> {
+        self.lines.content_of_lines(&self.text, line..=line)
+    }
+
+    /// Number of characters in the document.
+    ///
+    /// This and the other counts are computed in one pass on first use and cached until the next
+    /// edit.
+    pub fn char_count(&self) -> usize {
+        self.counts().chars
+    }
+
+    /// Length of the document in UTF-16 code units, as used by many editor protocols.
+    pub fn utf16_len(&self) -> usize {
+        self.counts().utf16
+    }
+
+    /// Number of characters on a line, including its terminator, or [None] if the line does not
+    /// exist.
+    pub fn line_char_count(&self, line: usize) -> Option<usize> {
+        let index = line.checked_sub(1)?;
+        self.counts().lines.get(index).map(|&(chars, _)| chars)
+    }
+
+    /// Length of a line in UTF-16 code units, including its terminator, or [None] if the line
+    /// does not exist.
+    pub fn line_utf16_len(&self, line: usize) -> Option<usize> {
+        let index = line.checked_sub(1)?;
+        self.counts().lines.get(index).map(|&(_, utf16)| utf16)
+    }
+
+    /// Take the text out of the document.
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    /// Replace the whole text of the document, indexing it from scratch.
+    ///
+    /// Anchors move to the start or end of the new text according to their bias, except that
+    /// anchors at the end of the old text stay at the end.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.counts.take();
+        let old_len = self.text.len();
+        self.text = text.into();
+        self.lines = Lines::parse(&self.text);
+        self.move_anchors(0..old_len, self.text.len());
+    }
+
+    /// Create an [Anchor] at a byte offset, which must lie on a character boundary, to track the
+    /// position through later edits.
+    ///
+    /// Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) past the end of the text,
+    /// which is itself a valid offset, or [NotCharBoundary](LinesError::NotCharBoundary) inside a
+    /// character.
+    pub fn anchor(&mut self, offset: usize, bias: Bias) -> Result<Anchor, LinesError> {
+        check_range(&self.text, &(offset..offset))?;
+        self.anchors.push(Some((offset, bias)));
+        Ok(Anchor(self.anchors.len() - 1))
+    }
+
+    /// Current byte offset of an anchor, or [None] if it was removed or belongs to another
+    /// document.
+    pub fn anchor_offset(&self, anchor: Anchor) -> Option<usize> {
+        self.anchors
+            .get(anchor.0)
+            .copied()
+            .flatten()
+            .map(|(offset, _)| offset)
+    }
+
+    /// Stop tracking an anchor.
+    pub fn remove_anchor(&mut self, anchor: Anchor) {
+        if let Some(slot) = self.anchors.get_mut(anchor.0) {
+            *slot = None;
+        }
+    }
+
+    /// Replace the content of a line, keeping its terminator.
+    ///
+    /// The content may itself contain line terminators, splitting the line. Returns the byte
+    /// range of the new content, or [LineOutOfBounds](LinesError::LineOutOfBounds) if the line
+    /// does not exist.
+    pub fn set_line(&mut self, line: usize, content: &str) -> Result<Range<usize>, LinesError> {
+        let range = self.lines.span_of_lines(line..=line, false)?;
+        Ok(self.splice(range, content))
+    }
+
+    /// Insert a line before the given line, or after the last line when given one past
+    /// [num_lines](Lines::num_lines).
+    ///
+    /// The line is terminated with the document's most common [line ending](Lines::line_ending),
+    /// or `\n` if it has none. Returns the byte range of the inserted text, including the added
+    /// terminator, or [LineOutOfBounds](LinesError::LineOutOfBounds) for any other line.
+    pub fn insert_line(&mut self, line: usize, content: &str) -> Result<Range<usize>, LinesError> {
+        let ending = self.line_ending();
+        let last = self.lines.line(self.lines.num_lines().wrapping_sub(1));
+        let (at, inserted) = match line.checked_sub(1).map(|index| self.lines.line(index)) {
+            Some(Some(before)) => (before.start, format!("{content}{ending}")),
+            Some(None) if line == self.lines.num_lines() + 1 => match last {
+                Some(last) if last.content_end == last.end => {
+                    (last.end, format!("{ending}{content}"))
+                }
+                _ => (self.text.len(), format!("{content}{ending}")),
+            },
+            _ => return Err(LinesError::LineOutOfBounds),
+        };
+        Ok(self.splice(at..at, &inserted))
+    }
+
+    /// Delete a range of lines along with their terminators.
+    ///
+    /// Returns the empty byte range where the lines were, or
+    /// [LineOutOfBounds](LinesError::LineOutOfBounds) as for
+    /// [text_of_lines](Lines::text_of_lines).
+    pub fn delete_lines(
+        &mut self,
+        lines: RangeInclusive<usize>,
+    ) -> Result<Range<usize>, LinesError> {
+        let range = self.lines.span_of_lines(lines, true)?;
+        Ok(self.splice(range, ""))
+    }
+
+    /// Replace a byte range of the text with `new_text`, as for an incremental change from an
+    /// editor.
+    ///
+    /// Returns the byte range of the new text, or
+    /// [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if the range is reversed or extends past
+    /// the end of the text, or [NotCharBoundary](LinesError::NotCharBoundary) if it does not lie on
+    /// character boundaries.
+    ///
+    /// ```
+    /// use line_position::document::TextDocument;
+    ///
+    /// let mut document = TextDocument::new("fn a() {}\n");
+    /// assert_eq!(document.apply_edit(8..8, "\n    b();\n").unwrap(), 8..18);
+    /// assert_eq!(document.text(), "fn a() {\n    b();\n}\n");
+    /// ```
+    pub fn apply_edit(
+        &mut self,
+        range: Range<usize>,
+        new_text: &str,
+    ) -> Result<Range<usize>, LinesError> {
+        check_range(&self.text, &range)?;
+        Ok(self.splice(range, new_text))
+    }
+
+    /// Append text to the end of the document, as when following a growing log file.
+    ///
+    /// Only the last line is rescanned. Returns the byte range of the appended text.
+    pub fn append(&mut self, text: &str) -> Range<usize> {
+        let end = self.text.len();
+        self.splice(end..end, text)
+    }
+
+    /// Join a range of lines into one, replacing each terminator between them with `separator`.
+    ///
+    /// With `trim_whitespace`, whitespace around each joint is removed too, as editors do when
+    /// joining lines of code. The terminator of the last line is kept. Returns a [JoinedLines]
+    /// for remapping offsets into the old text, or
+    /// [LineOutOfBounds](LinesError::LineOutOfBounds) as for [text_of_lines](Lines::text_of_lines).
+    ///
+    /// ```
+    /// use line_position::document::TextDocument;
+    ///
+    /// let mut document = TextDocument::new("call(\n    a,\n    b\n)\n");
+    /// let joined = document.join_lines(1..=4, " ", true).unwrap();
+    /// assert_eq!(document.text(), "call( a, b )\n");
+    /// assert_eq!(joined.remap(10), Some(6), "a moved to the joined line");
+    /// assert_eq!(joined.remap(5), None, "the first terminator is gone");
+    /// ```
+    pub fn join_lines(
+        &mut self,
+        lines: RangeInclusive<usize>,
+        separator: &str,
+        trim_whitespace: bool,
+    ) -> Result<JoinedLines, LinesError> {
+        let range = self.lines.span_of_lines(lines.clone(), false)?;
+        let (first, last) = (*lines.start(), *lines.end());
+
+        let mut joined = String::new();
+        let mut segments = Vec::new();
+        for line in first..=last {
+            let line_range = self.lines.span_of_lines(line..=line, false)?;
+            let content = &self.text[line_range.clone()];
+            let kept = match trim_whitespace {
+                true if line == first => content.trim_end(),
+                true if line == last => content.trim_start(),
+                true => content.trim(),
+                false => content,
+            };
+            let indent = match trim_whitespace && line != first {
+                true => content.len() - content.trim_start().len(),
+                false => 0,
+            };
+            let kept_start = line_range.start + indent;
+            if line != first {
+                joined.push_str(separator);
+            }
+            segments.push((
+                kept_start..kept_start + kept.len(),
+                range.start + joined.len(),
+            ));
+            joined.push_str(kept);
+        }
+
+        let shift = joined.len() as isize - range.len() as isize;
+        let span = self.splice(range.clone(), &joined);
+        Ok(JoinedLines {
+            old: range,
+            span,
+            segments,
+            shift,
+        })
+    }
+
+    /// Replace a byte range of the text, which must lie on character boundaries, and update the
+    /// index. Returns the byte range of the replacement in the new text.
+    pub(crate) fn splice(&mut self, range: Range<usize>, replacement: &str) -> Range<usize> {
+        self.counts.take();
+        self.text.replace_range(range.clone(), replacement);
+        self.lines
+            .splice(self.text.as_bytes(), range.clone(), replacement.len());
+        self.move_anchors(range.clone(), replacement.len());
+        range.start..range.start + replacement.len()
+    }
+
+    /// Move anchors after `range` of the text was replaced by `inserted` bytes.
+    fn move_anchors(&mut self, range: Range<usize>, inserted: usize) {
+        for (offset, bias) in self.anchors.iter_mut().flatten() {
+            *offset = remap(*offset, *bias, range.clone(), inserted);
+        }
+    }
+
+    /// Cached counts, computing them if needed.
+    fn counts(&self) -> &Counts {
+        self.counts.get_or_init(|| {
+            let lines: Vec<(usize, usize)> = self
+                .lines
+                .lines()
+                .map(|line| {
+                    let text = &self.text[line.start..line.end];
+                    (text.chars().count(), text.encode_utf16().count())
+                })
+                .collect();
+            Counts {
+                chars: lines.iter().map(|&(chars, _)| chars).sum(),
+                utf16: lines.iter().map(|&(_, utf16)| utf16).sum(),
+                lines,
+            }
+        })
+    }
+
+    /// Line delimiter used for lines the document adds.
+    fn line_ending(&self) -> &'static str {
+        self.lines.line_ending().map_or("\n", LineEnding::as_str)
+    }
+}
+
+/// Map offsets into a text to the same positions after a set of edits was applied to it.
+///
+/// Each edit is the byte range it replaced in the original text, together with the length of
+/// its replacement. Edits must be sorted and must not overlap. Offsets move as
+/// [anchors](Anchor) with the given bias do and may be in any order; the results are in the same
+/// order. This takes time proportional to sorting the offsets plus the number of edits, rather
+/// than their product.
+///
+/// ```
+/// use line_position::{document::remap_offsets, Bias};
+///
+/// // "let x=1;" formatted to "let x = 1;".
+/// let edits = [(5..5, 1), (6..6, 1)];
+/// assert_eq!(remap_offsets(&edits, &[7, 0, 5, 6], Bias::Forward), [9, 0, 6, 8]);
+/// ```
+pub fn remap_offsets(edits: &[(Range<usize>, usize)], offsets: &[usize], bias: Bias) -> Vec<usize> {
+    debug_assert!(
+        edits
+            .windows(2)
+            .all(|pair| pair[0].0.end <= pair[1].0.start),
+        "edits must be sorted and must not overlap"
+    );
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_unstable_by_key(|&index| offsets[index]);
+
+    let mut remapped = vec![0; offsets.len()];
+    let mut edits = edits.iter().peekable();
+    let mut shift: isize = 0;
+    for index in order {
+        let offset = offsets[index];
+        // Edits that end before the offset, or that replaced text ending just before it.
+        while let Some((range, inserted)) = edits
+            .next_if(|(range, _)| offset > range.end || offset == range.end && !range.is_empty())
+        {
+            shift += *inserted as isize - range.len() as isize;
+        }
+        let local = match edits.peek() {
+            Some((range, inserted)) => remap(offset, bias, range.clone(), *inserted),
+            None => offset,
+        };
+        remapped[index] = local.wrapping_add_signed(shift);
+    }
+    remapped
+}
+
+/// Map an offset across the replacement of `range` by `inserted` bytes.
+///
+/// Offsets before the range stay, offsets after it shift, and offsets at an insertion point or
+/// inside the replaced text move to the start or end of the new text according to `bias`.
+fn remap(offset: usize, bias: Bias, range: Range<usize>, inserted: usize) -> usize {
+    let replaced = offset >= range.start && offset < range.end;
+    match offset {
+        _ if offset < range.start => offset,
+        _ if replaced || offset == range.start && range.is_empty() => match bias {
+            Bias::Backward => range.start,
+            Bias::Forward => range.start + inserted,
+        },
+        _ => offset - range.len() + inserted,
+    }
+}
+
+/// Result of [join_lines](TextDocument::join_lines), describing where text moved.
+#[derive(Debug, Clone)]
+pub struct JoinedLines {
+    old: Range<usize>,
+    span: Range<usize>,
+    segments: Vec<(Range<usize>, usize)>,
+    shift: isize,
+}
+
+impl JoinedLines {
+    /// Byte range of the joined line content in the new text.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Map an offset into the text before the join to the same byte in the text after it.
+    ///
+    /// Returns [None] for bytes that were removed, that is terminators and trimmed whitespace.
+    /// Offsets past the joined lines shift with the text.
+    pub fn remap(&self, offset: usize) -> Option<usize> {
+        match offset {
+            _ if offset < self.old.start => Some(offset),
+            _ if offset >= self.old.end => offset.checked_add_signed(self.shift),
+            _ => self
+                .segments
+                .iter()
+                .find(|(old, _)| old.contains(&offset))
+                .map(|(old, new_start)| new_start + offset - old.start),
+        }
+    }
+}
+
+impl Lines {
+    /// Update the index after `range` of the old text was replaced by `new_text`, giving
+    /// `source`, the whole text after the edit.
+    ///
+    /// Only the lines touched by the edit are rescanned and the offsets of later lines are
+    /// shifted, so keeping the index in sync with an editor costs time proportional to the
+    /// changed lines rather than the whole text. [TextDocument] does this for text it owns.
+    ///
+    /// Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if the range is not within
+    /// the old text, or with [StaleIndex](LinesError::StaleIndex) if `source` does not contain
+    /// `new_text` in place of the range.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let mut source = String::from("abc\ndef\n");
+    /// let mut lines = Lines::parse(&source);
+    /// source.replace_range(1..5, "X\nY\nZ");
+    /// lines.apply_edit(&source, 1..5, "X\nY\nZ").unwrap();
+    /// assert_eq!(lines.num_lines(), 3);
+    /// assert_eq!(lines.position(6).unwrap().line(), 3);
+    /// ```
+    pub fn apply_edit(
+        &mut self,
+        source: &str,
+        range: Range<usize>,
+        new_text: &str,
+    ) -> Result<(), LinesError> {
+        if range.end > self.total_len() {
+            return Err(LinesError::OffsetOutOfBounds {
+                offset: range.end,
+                max: self.total_len(),
+            });
+        }
+        if range.start > range.end {
+            return Err(LinesError::OffsetOutOfBounds {
+                offset: range.start,
+                max: range.end,
+            });
+        }
+        let inserted = range.start..range.start + new_text.len();
+        match source.len() == self.total_len() - range.len() + new_text.len()
+            && source.get(inserted) == Some(new_text)
+        {
+            true => {
+                self.splice(source.as_bytes(), range, new_text.len());
+                Ok(())
+            }
+            false => Err(LinesError::StaleIndex),
+        }
+    }
+
+    /// Update the index after `range` of the old text was replaced by `inserted` bytes, giving
+    /// `text`, the whole new text.
+    pub(crate) fn splice(&mut self, text: &[u8], range: Range<usize>, inserted: usize) {
+        self.bom = text.starts_with(BOM);
+
+        // Rescan from the start of the line containing the edit through the line containing its
+        // end, which is unchanged in the new text and so still ends where it did, shifted.
+        let mut first = match self.line_index(range.start) {
+            Some(index) => index,
+            None if self.has_trailing_newline() => self.num_lines(),
+            None => self.num_lines().saturating_sub(1),
+        };
+        // A `\n` inserted at the start of the line can join a lone `\r` before it into `\r\n`.
+        if first > 0 && self.endings.get(first - 1) == Some(&LineEnding::Cr) {
+            first -= 1;
+        }
+        let start = self.starts.get(first).copied().unwrap_or(self.len);
+        let mut after = self
+            .line_index(range.end)
+            .map_or(self.num_lines(), |index| index + 1);
+        let shift = |offset: usize| offset + inserted - (range.end - range.start);
+
+        loop {
+            let old_end = self.starts.get(after).copied().unwrap_or(self.len);
+            let region = &text[start..shift(old_end)];
+            let mut region = Lines::scan_into(region, self.breaks, Lines::default(), usize::MAX);
+            let terminated = region.endings.len() == region.starts.len();
+
+            // An edit to a terminator can merge the region into the following line.
+            if !terminated && after < self.num_lines() {
+                after += 1;
+                continue;
+            }
+
+            for line_start in &mut region.starts {
+                *line_start += start;
+            }
+            for line_start in &mut self.starts[after..] {
+                *line_start = shift(*line_start);
+            }
+            self.starts.splice(first..after, region.starts);
+            let endings = first..after.min(self.endings.len());
+            self.endings.splice(endings, region.endings);
+            self.len = text.len();
+            debug_assert_eq!(self.check_invariants(), Ok(()));
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    /// Check that the incrementally updated index matches a fresh one.
+    fn assert_reindexed(document: &TextDocument) {
+        assert_eq!(
+            document.lines(),
+            &Lines::parse(document.text()),
+            "index of {:?}",
+            document.text()
+        );
+    }
+
+    #[test]
+    fn splice() {
+        let cases: &[(&str, Range<usize>, &str)] = &[
+            ("abc\ndef\n", 1..2, "X"),
+            ("abc\ndef\n", 3..4, ""),
+            ("abc\ndef\n", 8..8, "ghi"),
+            ("abc\ndef", 7..7, "\nghi\n"),
+            ("abc\ndef\n", 0..8, ""),
+            ("", 0..0, "a\nb"),
+            ("abc\r\ndef\r\n", 3..4, ""),
+            ("abc\r\ndef\r\n", 4..4, "\r\n"),
+            ("abc\r\ndef\r\n", 3..4, "\r\nx\r"),
+            ("abc\r\ndef\r\nghi", 5..10, ""),
+            ("abc\r\ndef", 3..5, "\n"),
+            ("abc\ndef\n", 3..3, "\r"),
+            ("ab\ncd\nef\ngh\n", 4..7, "X\nY\nZ"),
+        ];
+        for (text, range, replacement) in cases {
+            let mut document = TextDocument::new(*text);
+            let replaced = document.splice(range.clone(), replacement);
+            assert_eq!(&document.text()[replaced], *replacement);
+            assert_reindexed(&document);
+        }
+    }
+
+    #[test]
+    fn apply_edit() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("aé\nb\n");
+        assert_eq!(document.apply_edit(3..3, "\r")?, 3..4);
+        assert_eq!(document.text(), "aé\r\nb\n");
+        assert_reindexed(&document);
+        assert!(matches!(
+            document.apply_edit(2..3, ""),
+            Err(LinesError::NotCharBoundary { offset: 2 })
+        ));
+        assert!(matches!(
+            document.apply_edit(4..99, ""),
+            Err(LinesError::OffsetOutOfBounds { offset: 99, max: 7 })
+        ));
+        let (start, end) = (5, 4);
+        assert!(matches!(
+            document.apply_edit(start..end, ""),
+            Err(LinesError::OffsetOutOfBounds { offset: 5, max: 4 })
+        ));
+
+        let mut lines = Lines::parse("ab\ncd\n");
+        assert!(matches!(
+            lines.apply_edit("ab\nXd\n", 3..4, "Y"),
+            Err(LinesError::StaleIndex)
+        ));
+        assert!(matches!(
+            lines.apply_edit("ab\n", 3..9, ""),
+            Err(LinesError::OffsetOutOfBounds { offset: 9, max: 6 })
+        ));
+        lines.apply_edit("ab\n", 3..6, "")?;
+        assert_eq!(lines, Lines::parse("ab\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_edit_lone_cr() -> Result<(), LinesError> {
+        let options = ParseOptions::new().lone_cr(true);
+        let cases: &[(&str, Range<usize>, &str)] = &[
+            ("a\rb\rc", 0..0, "x"),
+            ("a\rb\rc", 2..2, "\n"),
+            ("a\rb\r", 4..4, "\n"),
+            ("a\rx\nb", 2..3, ""),
+            ("a\r\nb", 2..3, ""),
+            ("ab\ncd", 2..3, "\r"),
+        ];
+        for (text, range, replacement) in cases {
+            let mut source = String::from(*text);
+            let mut lines = options.parse(&source)?;
+            source.replace_range(range.clone(), replacement);
+            lines.apply_edit(&source, range.clone(), replacement)?;
+            assert_eq!(lines, options.parse(&source)?, "index of {source:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_edit_unicode_breaks() -> Result<(), LinesError> {
+        let options = ParseOptions::new().unicode_breaks(true);
+        let cases: &[(&str, Range<usize>, &str)] = &[
+            ("a\u{2028}b\u{2029}c", 0..0, "x"),
+            ("a\u{2028}b", 4..4, "\u{85}"),
+            ("a\u{85}b\nc", 1..3, ""),
+            ("a\u{c}b\u{b}c", 2..3, "d\u{2029}e"),
+            ("ab\ncd", 1..4, "\u{2028}"),
+        ];
+        for (text, range, replacement) in cases {
+            let mut source = String::from(*text);
+            let mut lines = options.parse(&source)?;
+            source.replace_range(range.clone(), replacement);
+            lines.apply_edit(&source, range.clone(), replacement)?;
+            assert_eq!(lines, options.parse(&source)?, "index of {source:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn versions() {
+        let mut document = TextDocument::new("a\n").with_version(3);
+        document.append("b\n");
+        assert_eq!(document.version(), 3, "edits keep the version");
+
+        document.set_text("c\r\nd");
+        document.set_version(4);
+        assert_eq!((document.text(), document.version()), ("c\r\nd", 4));
+        assert_eq!(document.utf16_len(), 4);
+        assert_reindexed(&document);
+    }
+
+    #[test]
+    fn anchors() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("abcdef");
+        let anchors = [
+            document.anchor(0, Bias::Backward)?,
+            document.anchor(2, Bias::Forward)?,
+            document.anchor(3, Bias::Backward)?,
+            document.anchor(4, Bias::Forward)?,
+            document.anchor(6, Bias::Backward)?,
+        ];
+        let offsets =
+            |document: &TextDocument| anchors.map(|anchor| document.anchor_offset(anchor));
+
+        document.apply_edit(2..4, "XYZ")?;
+        assert_eq!(document.text(), "abXYZef");
+        assert_eq!(offsets(&document), [0, 5, 2, 5, 7].map(Some));
+
+        document.remove_anchor(anchors[0]);
+        document.set_text("new");
+        assert_eq!(
+            offsets(&document),
+            [None, Some(3), Some(0), Some(3), Some(3)]
+        );
+
+        assert!(matches!(
+            TextDocument::new("é").anchor(1, Bias::Forward),
+            Err(LinesError::NotCharBoundary { offset: 1 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn remap_offsets_matches_anchors() -> Result<(), LinesError> {
+        let text = "fn  main(){let x=1;}";
+        let edits = [(2..4, 1), (10..10, 1), (11..11, 5), (16..17, 3)];
+        let offsets: Vec<usize> = (0..=text.len()).rev().collect();
+
+        for bias in [Bias::Backward, Bias::Forward] {
+            let mut document = TextDocument::new(text);
+            let anchors: Vec<_> = offsets
+                .iter()
+                .map(|&offset| document.anchor(offset, bias))
+                .collect::<Result<_, _>>()?;
+            for (range, inserted) in edits.iter().rev() {
+                document.apply_edit(range.clone(), &"_".repeat(*inserted))?;
+            }
+
+            let expected: Vec<_> = anchors
+                .iter()
+                .map(|&anchor| document.anchor_offset(anchor).unwrap())
+                .collect();
+            assert_eq!(remap_offsets(&edits, &offsets, bias), expected, "{bias:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn line_text() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("one\ntwo");
+        assert_eq!(document.line_text(1)?, "one\n");
+        assert_eq!(document.line_text(2)?, "two");
+        assert!(matches!(
+            document.line_content(3),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        document.set_line(1, "1")?;
+        assert_eq!(document.line_content(1)?, "1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn counts() {
+        let mut document = TextDocument::new("aé\n😀\r");
+
+        assert_eq!((document.char_count(), document.utf16_len()), (5, 6));
+        assert_eq!(document.line_char_count(1), Some(3));
+        assert_eq!(document.line_utf16_len(2), Some(3));
+        assert_eq!(document.line_char_count(3), None);
+        assert_eq!(document.line_utf16_len(0), None);
+
+        document.append("\n€");
+        assert_eq!((document.char_count(), document.utf16_len()), (7, 8));
+        assert_eq!(
+            (document.line_char_count(1), document.line_char_count(2)),
+            (Some(3), Some(3)),
+            "each line keeps its own terminator"
+        );
+        assert_eq!(TextDocument::new("").char_count(), 0);
+    }
+
+    #[test]
+    fn append() {
+        let mut document = TextDocument::new("a\nb");
+
+        assert_eq!(document.append("c\nd\n"), 3..7);
+        assert_eq!(document.text(), "a\nbc\nd\n");
+        assert_reindexed(&document);
+        document.append("\r\n");
+        assert_reindexed(&document);
+    }
+
+    #[test]
+    fn set_line() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("one\r\ntwo\r\nthree");
+
+        assert_eq!(document.set_line(2, "2")?, 5..6);
+        assert_reindexed(&document);
+        document.set_line(3, "3\r\nfour")?;
+        assert_eq!(document.text(), "one\r\n2\r\n3\r\nfour");
+        assert_reindexed(&document);
+        assert!(matches!(
+            document.set_line(5, ""),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn insert_line() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("b\r\nc");
+
+        assert_eq!(document.insert_line(1, "a")?, 0..3);
+        assert_eq!(document.insert_line(4, "d")?, 7..10);
+        assert_eq!(document.text(), "a\r\nb\r\nc\r\nd");
+        assert_reindexed(&document);
+
+        let mut document = TextDocument::new("a\n");
+        document.insert_line(2, "b")?;
+        assert_eq!(document.text(), "a\nb\n");
+        assert_reindexed(&document);
+        assert!(matches!(
+            document.insert_line(0, ""),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            document.insert_line(4, ""),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        let mut document = TextDocument::new("");
+        document.insert_line(1, "a")?;
+        assert_eq!(document.text(), "a\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn join_lines() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("a \r\n  b\r\n\r\nc\r\nd");
+        let joined = document.join_lines(1..=4, ", ", true)?;
+
+        assert_eq!(document.text(), "a, b, , c\r\nd");
+        assert_eq!(joined.span(), 0..9);
+        assert_reindexed(&document);
+        let remapped: Vec<Option<usize>> = (0..=16).map(|offset| joined.remap(offset)).collect();
+        assert_eq!(
+            remapped,
+            [
+                Some(0),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(3),
+                None,
+                None,
+                None,
+                None,
+                Some(8),
+                Some(9),
+                Some(10),
+                Some(11),
+                Some(12),
+                Some(13),
+            ]
+        );
+
+        let mut document = TextDocument::new("a \n b\n");
+        let joined = document.join_lines(1..=2, "", false)?;
+        assert_eq!(document.text(), "a  b\n");
+        assert_eq!(joined.remap(3), Some(2));
+        assert!(matches!(
+            document.join_lines(1..=2, "", false),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn delete_lines() -> Result<(), LinesError> {
+        let mut document = TextDocument::new("a\nb\nc\nd");
+
+        assert_eq!(document.delete_lines(2..=3)?, 2..2);
+        assert_eq!(document.text(), "a\nd");
+        assert_reindexed(&document);
+        document.delete_lines(2..=2)?;
+        assert_eq!(document.text(), "a\n");
+        assert_reindexed(&document);
+        assert!(matches!(
+            document.delete_lines(1..=2),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//! Columns counted in UTF-16 code units or characters rather than bytes, as used by most
+//! editors and the Language Server Protocol.
+//!
+//! These scan the line up to the column, which is fine for ordinary source code. For very long
+//! lines, such as those of minified files, use a [ColumnIndex](crate::columns::ColumnIndex).
+
+use crate::{LinePosition, Lines, LinesError, PositionEncoding};
+
+impl PositionEncoding {
+    /// Number of units a character takes in this encoding.
+    fn len(self, c: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => c.len_utf8(),
+            PositionEncoding::Utf16 => c.len_utf16(),
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+}
+
+impl Lines {
+    /// Lookup the position of a byte offset of `source`, the text the index was built from, with
+    /// the offset within the line counted in UTF-16 code units.
+    ///
+    /// An offset inside a multi-byte character is treated as the start of that character.
+    /// Fails like [position](Lines::position), or with [StaleIndex](LinesError::StaleIndex) if
+    /// `source` does not match the index.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let source = "x\n😀 = 1;\n";
+    /// let lines = Lines::parse(source);
+    /// // The "=" follows an emoji, which is four bytes but two UTF-16 code units.
+    /// let position = lines.position_utf16(source, 7).unwrap();
+    /// assert_eq!((position.line(), position.offset()), (2, 3));
+    /// assert_eq!(lines.offset_of_utf16(source, 2, 3).unwrap(), 7);
+    /// ```
+    pub fn position_utf16(&self, source: &str, offset: usize) -> Result<LinePosition, LinesError> {
+        self.position_with_encoding(source, offset, PositionEncoding::Utf16)
+    }
+
+    /// Lookup the byte offset of a line number, starting with 1, and a column counted in UTF-16
+    /// code units, starting with 0.
+    ///
+    /// A column in the middle of a surrogate pair maps to the start of that character. The
+    /// column may point just past the line content. Fails with
+    /// [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist,
+    /// [ColumnOutOfBounds](LinesError::ColumnOutOfBounds) if the column is past the end of the
+    /// line content, or [StaleIndex](LinesError::StaleIndex) if `source` does not match the
+    /// index.
+    pub fn offset_of_utf16(
+        &self,
+        source: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<usize, LinesError> {
+        self.offset_of_with_encoding(source, line, column, PositionEncoding::Utf16)
+    }
+
+    /// Length in UTF-16 code units of the content of a line of `source`, the text the index was
+    /// built from, which is the largest column [offset_of_utf16](Lines::offset_of_utf16)
+    /// accepts on the line.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist, or
+    /// with [StaleIndex](LinesError::StaleIndex) if `source` does not match the index.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let source = "x\n😀 = 1;\n";
+    /// let lines = Lines::parse(source);
+    /// assert_eq!(lines.line_len_utf16(source, 2).unwrap(), 7);
+    /// assert_eq!(lines.line_len_chars(source, 2).unwrap(), 6);
+    /// ```
+    pub fn line_len_utf16(&self, source: &str, line: usize) -> Result<usize, LinesError> {
+        self.line_len_with_encoding(source, line, PositionEncoding::Utf16)
+    }
+
+    /// Length in characters of the content of a line of `source`, like
+    /// [line_len_utf16](Lines::line_len_utf16).
+    pub fn line_len_chars(&self, source: &str, line: usize) -> Result<usize, LinesError> {
+        self.line_len_with_encoding(source, line, PositionEncoding::Utf32)
+    }
+
+    /// Lookup the position of a byte offset of `source`, with the offset within the line counted
+    /// in characters, that is Unicode scalar values.
+    ///
+    /// This matches the `utf-32` position encoding of LSP 3.17. Offsets inside a character and
+    /// failures are handled as in [position_utf16](Lines::position_utf16).
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let source = "x\n😀 = 1;\n";
+    /// let lines = Lines::parse(source);
+    /// let position = lines.position_chars(source, 7).unwrap();
+    /// assert_eq!((position.line(), position.offset()), (2, 2));
+    /// assert_eq!(lines.offset_of_chars(source, 2, 2).unwrap(), 7);
+    /// ```
+    pub fn position_chars(&self, source: &str, offset: usize) -> Result<LinePosition, LinesError> {
+        self.position_with_encoding(source, offset, PositionEncoding::Utf32)
+    }
+
+    /// Lookup the byte offset of a line number, starting with 1, and a column counted in
+    /// characters, starting with 0.
+    ///
+    /// Fails as [offset_of_utf16](Lines::offset_of_utf16) does.
+    pub fn offset_of_chars(
+        &self,
+        source: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<usize, LinesError> {
+        self.offset_of_with_encoding(source, line, column, PositionEncoding::Utf32)
+    }
+
+    /// Lookup the position of a byte offset of `source`, with the offset within the line counted
+    /// in units of `encoding`.
+    ///
+    /// This lets a language server convert positions in whatever encoding it negotiated with the
+    /// client. Offsets inside a character and failures are handled as in
+    /// [position_utf16](Lines::position_utf16), also for [Utf8](PositionEncoding::Utf8).
+    ///
+    /// ```
+    /// use line_position::{Lines, PositionEncoding};
+    ///
+    /// let source = "😀 = 1;\n";
+    /// let lines = Lines::parse(source);
+    /// let columns = [PositionEncoding::Utf8, PositionEncoding::Utf16, PositionEncoding::Utf32]
+    ///     .map(|encoding| lines.position_with_encoding(source, 5, encoding).unwrap().offset());
+    /// assert_eq!(columns, [5, 3, 2]);
+    /// ```
+    pub fn position_with_encoding(
+        &self,
+        source: &str,
+        offset: usize,
+        encoding: PositionEncoding,
+    ) -> Result<LinePosition, LinesError> {
+        let position = self.position(offset)?;
+        let line_start = self.column_start(position.line() - 1);
+        if offset > source.len() {
+            return Err(LinesError::StaleIndex);
+        }
+        let mut end = offset;
+        while !source.is_char_boundary(end) {
+            end -= 1;
+        }
+        let prefix = source
+            .get(line_start.min(end)..end)
+            .ok_or(LinesError::StaleIndex)?;
+        let column = prefix.chars().map(|c| encoding.len(c)).sum();
+        Ok(LinePosition::new(position.line(), column))
+    }
+
+    /// Length in units of `encoding` of the content of a line of `source`, as in
+    /// [line_len_utf16](Lines::line_len_utf16).
+    pub fn line_len_with_encoding(
+        &self,
+        source: &str,
+        line: usize,
+        encoding: PositionEncoding,
+    ) -> Result<usize, LinesError> {
+        let index = line.checked_sub(1).ok_or(LinesError::LineOutOfBounds)?;
+        let line = self.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        let content = source
+            .get(self.column_start(index)..line.content_end)
+            .ok_or(LinesError::StaleIndex)?;
+        Ok(content.chars().map(|c| encoding.len(c)).sum())
+    }
+
+    /// Lookup the byte offset of a line number, starting with 1, and a column counted in units of
+    /// `encoding`, starting with 0.
+    ///
+    /// A column inside a character maps to the start of that character. Fails as
+    /// [offset_of_utf16](Lines::offset_of_utf16) does.
+    pub fn offset_of_with_encoding(
+        &self,
+        source: &str,
+        line: usize,
+        column: usize,
+        encoding: PositionEncoding,
+    ) -> Result<usize, LinesError> {
+        let index = line.checked_sub(1).ok_or(LinesError::LineOutOfBounds)?;
+        let line = self.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        let start = self.column_start(index);
+        let content = source
+            .get(start..line.content_end)
+            .ok_or(LinesError::StaleIndex)?;
+
+        let mut units = 0;
+        for (byte, c) in content.char_indices() {
+            units += encoding.len(c);
+            if units > column {
+                return Ok(start + byte);
+            }
+        }
+        match units == column {
+            true => Ok(line.content_end),
+            false => Err(LinesError::ColumnOutOfBounds),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseOptions;
+
+    #[test]
+    fn utf16_columns() -> Result<(), LinesError> {
+        let source = "aé😀b\r\n😀";
+        let lines = Lines::parse(source);
+
+        let columns: Vec<usize> = (0..source.len())
+            .map(|offset| Ok(lines.position_utf16(source, offset)?.offset()))
+            .collect::<Result<_, LinesError>>()?;
+        assert_eq!(columns, [0, 1, 1, 2, 2, 2, 2, 4, 5, 6, 0, 0, 0, 0]);
+
+        assert_eq!(
+            lines.offset_of_utf16(source, 1, 3)?,
+            3,
+            "inside surrogate pair"
+        );
+        assert_eq!(lines.offset_of_utf16(source, 1, 5)?, 8, "end of line");
+        assert_eq!(lines.offset_of_utf16(source, 2, 2)?, source.len());
+        assert!(matches!(
+            lines.offset_of_utf16(source, 1, 6),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.offset_of_utf16(source, 3, 0),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.position_utf16("a", 5),
+            Err(LinesError::StaleIndex)
+        ));
+
+        assert_eq!(lines.line_len_utf16(source, 1)?, 5);
+        assert_eq!(lines.line_len_chars(source, 1)?, 4);
+        assert_eq!(lines.line_len(1)?, 8);
+        assert_eq!(lines.line_len_utf16(source, 2)?, 2);
+        assert!(matches!(
+            lines.line_len_chars(source, 3),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.line_len_utf16("a", 1),
+            Err(LinesError::StaleIndex)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn char_columns() -> Result<(), LinesError> {
+        let source = "aé😀b\n😀";
+        let lines = Lines::parse(source);
+
+        let columns: Vec<usize> = (0..source.len())
+            .map(|offset| Ok(lines.position_chars(source, offset)?.offset()))
+            .collect::<Result<_, LinesError>>()?;
+        assert_eq!(columns, [0, 1, 1, 2, 2, 2, 2, 3, 4, 0, 0, 0, 0]);
+
+        for column in 0..=4 {
+            let offset = lines.offset_of_chars(source, 1, column)?;
+            assert_eq!(lines.position_chars(source, offset)?.offset(), column);
+        }
+        assert!(matches!(
+            lines.offset_of_chars(source, 1, 5),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn skipped_bom() -> Result<(), LinesError> {
+        let source = "\u{FEFF}é😀\n";
+        let lines = ParseOptions::new().skip_bom(true).parse(source)?;
+
+        assert_eq!(lines.position_utf16(source, 1)?.offset(), 0);
+        assert_eq!(lines.position_utf16(source, 9)?.offset(), 3);
+        assert_eq!(lines.offset_of_utf16(source, 1, 1)?, 5);
+        assert_eq!(lines.offset_of_chars(source, 1, 2)?, 9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn encodings_round_trip() -> Result<(), LinesError> {
+        let source = "aé😀b\r\nx";
+        let lines = Lines::parse(source);
+
+        for encoding in [
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+            PositionEncoding::Utf32,
+        ] {
+            for offset in [0, 1, 3, 7, 8, 10] {
+                let position = lines.position_with_encoding(source, offset, encoding)?;
+                assert_eq!(
+                    lines.offset_of_with_encoding(
+                        source,
+                        position.line(),
+                        position.offset(),
+                        encoding
+                    )?,
+                    offset,
+                    "{encoding:?} at {offset}"
+                );
+            }
+        }
+        assert_eq!(
+            lines.offset_of_with_encoding(source, 1, 2, PositionEncoding::Utf8)?,
+            1,
+            "inside a character"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Folding ranges derived from indentation, for editors without a syntax-aware alternative.
+
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use crate::Lines;
+
+/// Width of the leading whitespace of a line's content, with tabs advancing to the next multiple
+/// of `tab_width`, or [None] if the line is blank.
+///
+/// ```
+/// use line_position::folding::indent_width;
+///
+/// assert_eq!(indent_width("    x", 4), Some(4));
+/// assert_eq!(indent_width(" \tx", 4), Some(4));
+/// assert_eq!(indent_width("  ", 4), None);
+/// ```
+pub fn indent_width(content: &str, tab_width: usize) -> Option<usize> {
+    let mut width = 0;
+    for c in content.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_width.max(1) - width % tab_width.max(1),
+            c if c.is_whitespace() => width += 1,
+            _ => return Some(width),
+        }
+    }
+    None
+}
+
+impl Lines {
+    /// Compute folding ranges from the indentation of `source`, the text this index was built
+    /// from, as one-indexed inclusive line ranges sorted by their first line.
+    ///
+    /// A range starts at a line followed by more deeply indented lines and ends at the last of
+    /// them, not counting trailing blank lines. Blank lines inside a block do not end it.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let source = "fn main() {\n    if x {\n        y();\n    }\n}\n";
+    /// let lines = Lines::parse(source);
+    /// assert_eq!(lines.folding_ranges_by_indent(source, 4), [1..=4, 2..=3]);
+    /// ```
+    pub fn folding_ranges_by_indent(
+        &self,
+        source: &str,
+        tab_width: usize,
+    ) -> Vec<RangeInclusive<usize>> {
+        let mut ranges = Vec::new();
+        // Lines that may start a range, with their indentation, innermost last.
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut last_non_blank = 0;
+
+        for (index, line) in self.lines().enumerate() {
+            let content = source.get(line.start..line.content_end).unwrap_or("");
+            let Some(indent) = indent_width(content, tab_width) else {
+                continue;
+            };
+            let number = index + 1;
+            while let Some(&(start, _)) = open.last().filter(|&&(_, open)| open >= indent) {
+                open.pop();
+                if last_non_blank > start {
+                    ranges.push(start..=last_non_blank);
+                }
+            }
+            open.push((number, indent));
+            last_non_blank = number;
+        }
+        for (start, _) in open {
+            if last_non_blank > start {
+                ranges.push(start..=last_non_blank);
+            }
+        }
+
+        ranges.sort_by_key(|range| *range.start());
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folding_ranges_by_indent() {
+        let source = "\
+a:
+  b:
+
+    c
+  d
+\te
+
+f
+";
+        let lines = Lines::parse(source);
+
+        assert_eq!(
+            lines.folding_ranges_by_indent(source, 2),
+            [1..=6, 2..=4],
+            "tab of width 2 matches d"
+        );
+        assert_eq!(
+            lines.folding_ranges_by_indent(source, 8),
+            [1..=6, 2..=4, 5..=6]
+        );
+        assert_eq!(
+            Lines::parse("a\r\n b\r\n").folding_ranges_by_indent("a\r\n b\r\n", 4),
+            [1..=2]
+        );
+        assert!(Lines::parse("").folding_ranges_by_indent("", 4).is_empty());
+    }
+}
@@ -0,0 +1,449 @@
+//! Formatting helpers for reporting positions to other tools.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::ops::{Range, RangeInclusive};
+
+use crate::{LineIndex, LinePosition, LinesError};
+
+/// Format a GitHub Actions `::error` workflow command for a byte range within a file.
+///
+/// Lines and columns in the output are one-indexed, as GitHub expects, and columns count bytes.
+/// The end position is that of the last byte covered by the range, so an empty range starts and
+/// ends at the same column. An empty range may sit at the end of the input, such as for an
+/// unexpected end of file. Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if the
+/// range is reversed or extends past the input.
+///
+/// The path and message are escaped as required by the workflow command syntax.
+///
+/// ```
+/// use line_position::{format::github_error, Lines};
+///
+/// let lines = Lines::parse("let x = 1;\nlet y = ;\n");
+/// let annotation = github_error(&lines, "src/main.rs", 19..20, "expected expression").unwrap();
+/// assert_eq!(
+///     annotation,
+///     "::error file=src/main.rs,line=2,col=9,endLine=2,endColumn=9::expected expression"
+/// );
+/// ```
+pub fn github_error(
+    lines: &(impl LineIndex + ?Sized),
+    path: &str,
+    range: Range<usize>,
+    message: &str,
+) -> Result<String, LinesError> {
+    let (start, end) = inclusive_positions(lines, range)?;
+    Ok(format!(
+        "::error file={},line={},col={},endLine={},endColumn={}::{}",
+        escape_property(path),
+        start.line(),
+        start.offset() + 1,
+        end.line(),
+        end.offset() + 1,
+        escape_data(message),
+    ))
+}
+
+/// Format a position as `path:line:column`, the location syntax most terminals and editors turn
+/// into a link.
+///
+/// Lines and columns are one-indexed and columns count bytes, as in
+/// [github_error](github_error).
+///
+/// ```
+/// use line_position::{format::location, LinePosition};
+///
+/// assert_eq!(location("src/main.rs", &LinePosition::new(2, 8)), "src/main.rs:2:9");
+/// ```
+pub fn location(path: &str, position: &LinePosition) -> String {
+    position.with_path(path).to_string()
+}
+
+/// A position displayed as `path:line:column`, created by
+/// [with_path](LinePosition::with_path).
+#[derive(Debug, Clone, Copy)]
+pub struct PathPosition<'a> {
+    pub(crate) path: &'a str,
+    pub(crate) position: LinePosition,
+}
+
+impl fmt::Display for PathPosition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.path, self.position)
+    }
+}
+
+/// Format a `vscode://file` URI that opens `path` at a position in Visual Studio Code.
+///
+/// The path should be absolute. Backslashes are turned into slashes and characters with a
+/// meaning in URIs are percent-encoded.
+///
+/// ```
+/// use line_position::{format::vscode_uri, LinePosition};
+///
+/// assert_eq!(
+///     vscode_uri("/home/me/my project/main.rs", &LinePosition::new(2, 8)),
+///     "vscode://file/home/me/my%20project/main.rs:2:9"
+/// );
+/// ```
+pub fn vscode_uri(path: &str, position: &LinePosition) -> String {
+    let path = encode_path(path);
+    let path = path.strip_prefix('/').unwrap_or(&path);
+    format!(
+        "vscode://file/{path}:{}:{}",
+        position.line(),
+        position.offset() + 1
+    )
+}
+
+/// Format a `file://` URI for an absolute path, as used for [hyperlink] targets.
+///
+/// ```
+/// use line_position::format::file_uri;
+///
+/// assert_eq!(file_uri("/tmp/a b.rs"), "file:///tmp/a%20b.rs");
+/// assert_eq!(file_uri(r"C:\src\a.rs"), "file:///C:/src/a.rs");
+/// ```
+pub fn file_uri(path: &str) -> String {
+    let path = encode_path(path);
+    match path.starts_with('/') {
+        true => format!("file://{path}"),
+        false => format!("file:///{path}"),
+    }
+}
+
+/// Wrap `text` in an OSC 8 escape sequence, making it a link to `uri` in terminals that support
+/// them and leaving it plain text elsewhere.
+///
+/// ```
+/// use line_position::format::hyperlink;
+///
+/// assert_eq!(
+///     hyperlink("file:///tmp/a.rs", "a.rs:1:1"),
+///     "\x1b]8;;file:///tmp/a.rs\x1b\\a.rs:1:1\x1b]8;;\x1b\\"
+/// );
+/// ```
+pub fn hyperlink(uri: &str, text: &str) -> String {
+    format!("\x1b]8;;{uri}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// Normalize path separators and percent-encode characters that would end or alter a URI path.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '\\' => encoded.push('/'),
+            '%' | ' ' | '#' | '?' | '"' | '<' | '>' | '`' | '{' | '}' | '[' | ']' | '^' | '|' => {
+                encoded.push_str(&format!("%{:02X}", c as u32))
+            }
+            c if c.is_control() => {
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    encoded.push_str(&format!("%{byte:02X}"));
+                }
+            }
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Look up the positions of the first and last bytes of a range.
+///
+/// An empty range yields the position of its start twice, which may be the end of the input.
+/// Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) for a reversed range.
+pub(crate) fn inclusive_positions(
+    lines: &(impl LineIndex + ?Sized),
+    range: Range<usize>,
+) -> Result<(LinePosition, LinePosition), LinesError> {
+    if range.start > range.end {
+        return Err(LinesError::OffsetOutOfBounds {
+            offset: range.start,
+            max: range.end,
+        });
+    }
+    match range.is_empty() {
+        true => {
+            let position = lines.position_inclusive(range.start)?;
+            Ok((position, position))
+        }
+        false => Ok((lines.position(range.start)?, lines.position(range.end - 1)?)),
+    }
+}
+
+/// Render the lines covered by a byte range of `source`, with a line-number gutter and carets
+/// under the covered text.
+///
+/// `source` must be the text `lines` was built from. An empty range is marked with a single
+/// caret at its start.
+///
+/// ```
+/// use line_position::{format::snippet, Lines};
+///
+/// let source = "let x = 1;\nlet y = ;\n";
+/// let lines = Lines::parse(source);
+/// assert_eq!(
+///     snippet(&lines, source, 19..20).unwrap(),
+///     "2 | let y = ;\n  |         ^\n"
+/// );
+/// ```
+pub fn snippet(
+    lines: &(impl LineIndex + ?Sized),
+    source: &str,
+    range: Range<usize>,
+) -> Result<String, LinesError> {
+    let (start, last) = inclusive_positions(lines, range.clone())?;
+    let gutter_width = last.line().to_string().len();
+    let mut output = String::new();
+
+    for line in start.line()..=last.line() {
+        // An empty range at the end of input ending with a terminator is on the line after it.
+        let content = match line > lines.num_lines() {
+            true => "",
+            false => line_content(lines, source, line)?,
+        };
+
+        let marked_start = match line == start.line() {
+            true => start.offset(),
+            false => 0,
+        };
+        let marked_end = match line == last.line() && !range.is_empty() {
+            true => last.offset() + 1,
+            false if line == last.line() => marked_start + 1,
+            false => content.len(),
+        };
+        let prefix = &content[..floor_char_boundary(content, marked_start)];
+        let marked = &content[prefix.len()..floor_char_boundary(content, marked_end)];
+
+        let padding: String = prefix
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat(marked.chars().count().max(1));
+        output.push_str(&format!("{line:>gutter_width$} | {content}\n"));
+        output.push_str(&format!("{:gutter_width$} | {padding}{carets}\n", ""));
+    }
+
+    Ok(output)
+}
+
+/// Render a range of lines of `source`, the text `lines` was built from, each prefixed with its
+/// line number in a gutter like that of [snippet].
+///
+/// ```
+/// use line_position::{format::numbered_lines, Lines};
+///
+/// let source = "a\nb\nc\n";
+/// let lines = Lines::parse(source);
+/// assert_eq!(numbered_lines(&lines, source, 2..=3).unwrap(), "2 | b\n3 | c\n");
+/// ```
+pub fn numbered_lines(
+    lines: &(impl LineIndex + ?Sized),
+    source: &str,
+    range: RangeInclusive<usize>,
+) -> Result<String, LinesError> {
+    let gutter_width = range.end().to_string().len();
+    let mut output = String::new();
+    for line in range {
+        let content = line_content(lines, source, line)?;
+        output.push_str(&format!("{line:>gutter_width$} | {content}\n"));
+    }
+    Ok(output)
+}
+
+/// Content of a line of `source`, without its terminator.
+fn line_content<'a>(
+    lines: &(impl LineIndex + ?Sized),
+    source: &'a str,
+    line: usize,
+) -> Result<&'a str, LinesError> {
+    let text = source
+        .get(lines.line_span(line)?)
+        .ok_or(LinesError::StaleIndex)?;
+    let content = text.strip_suffix('\n').unwrap_or(text);
+    Ok(content.strip_suffix('\r').unwrap_or(content))
+}
+
+/// Largest character boundary of `text` at or before `index`, clamped to its length.
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Escape the message portion of a workflow command.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command.
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lines;
+
+    #[test]
+    fn display_positions() -> Result<(), LinesError> {
+        let lines = Lines::parse("ab\ncd");
+        let position = lines.position(4)?;
+
+        assert_eq!(position.to_string(), "2:2");
+        assert_eq!(
+            format!("error at {}", position.with_path("a.txt")),
+            "error at a.txt:2:2"
+        );
+        assert_eq!(location("a.txt", &position), "a.txt:2:2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn github_error_multi_line() -> Result<(), LinesError> {
+        let lines = Lines::parse("abc\ndef\nghi\n");
+        let annotation = github_error(&lines, "a.txt", 1..10, "bad")?;
+
+        assert_eq!(
+            annotation,
+            "::error file=a.txt,line=1,col=2,endLine=3,endColumn=2::bad"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn github_error_escaping() -> Result<(), LinesError> {
+        let lines = Lines::parse("abc");
+        let annotation = github_error(&lines, "a,b:c.txt", 0..0, "100%\nsure")?;
+
+        assert_eq!(
+            annotation,
+            "::error file=a%2Cb%3Ac.txt,line=1,col=1,endLine=1,endColumn=1::100%25%0Asure"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn snippet_multi_line() -> Result<(), LinesError> {
+        let source = "fn main() {\n\tfoo(é);\n}\n";
+        let lines = Lines::parse(source);
+
+        assert_eq!(
+            snippet(&lines, source, 13..19)?,
+            "2 | \tfoo(é);\n  | \t^^^^^\n"
+        );
+        assert_eq!(
+            snippet(&lines, source, 10..23)?,
+            "1 | fn main() {\n  |           ^\n2 | \tfoo(é);\n  | ^^^^^^^^\n3 | }\n  | ^\n"
+        );
+        assert_eq!(
+            snippet(&lines, source, 11..11)?,
+            "1 | fn main() {\n  |            ^\n",
+            "empty range at the terminator"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn numbered_lines() -> Result<(), LinesError> {
+        let source: String = (1..=10).map(|line| format!("line {line}\r\n")).collect();
+        let lines = Lines::parse(&source);
+
+        assert_eq!(
+            super::numbered_lines(&lines, &source, 9..=10)?,
+            " 9 | line 9\n10 | line 10\n"
+        );
+        assert!(matches!(
+            super::numbered_lines(&lines, &source, 10..=11),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            super::numbered_lines(&lines, "short", 1..=2),
+            Err(LinesError::StaleIndex)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn links() {
+        let position = LinePosition::new(10, 0);
+
+        assert_eq!(location("a.rs", &position), "a.rs:10:1");
+        assert_eq!(
+            vscode_uri(r"C:\Users\me\100%.rs", &position),
+            "vscode://file/C:/Users/me/100%25.rs:10:1"
+        );
+        assert_eq!(file_uri("/a#b?.rs"), "file:///a%23b%3F.rs");
+        assert_eq!(file_uri("/tab\there"), "file:///tab%09here");
+        assert_eq!(
+            hyperlink(&file_uri("/a.rs"), &location("a.rs", &position)),
+            "\x1b]8;;file:///a.rs\x1b\\a.rs:10:1\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn github_error_out_of_bounds() {
+        let lines = Lines::parse("abc");
+        assert!(github_error(&lines, "a.txt", 2..4, "bad").is_err());
+        let (start, end) = (2, 1);
+        assert!(matches!(
+            github_error(&lines, "a.txt", start..end, "reversed"),
+            Err(LinesError::OffsetOutOfBounds { offset: 2, max: 1 })
+        ));
+    }
+
+    #[test]
+    fn github_error_at_end_of_input() -> Result<(), LinesError> {
+        let unterminated = Lines::parse("abc");
+        assert_eq!(
+            github_error(&unterminated, "a.txt", 3..3, "unexpected EOF")?,
+            "::error file=a.txt,line=1,col=4,endLine=1,endColumn=4::unexpected EOF"
+        );
+        let terminated = Lines::parse("abc\r\n");
+        assert_eq!(
+            github_error(&terminated, "a.txt", 5..5, "unexpected EOF")?,
+            "::error file=a.txt,line=2,col=1,endLine=2,endColumn=1::unexpected EOF"
+        );
+        assert_eq!(
+            github_error(&Lines::parse(""), "a.txt", 0..0, "empty")?,
+            "::error file=a.txt,line=1,col=1,endLine=1,endColumn=1::empty"
+        );
+        assert_eq!(
+            snippet(&terminated, "abc\r\n", 5..5)?,
+            "2 | \n  | ^\n",
+            "the line after the final terminator"
+        );
+
+        let compact = crate::compact::CompactLines::parse("abc\r\n")?;
+        for offset in 0..=5 {
+            assert_eq!(
+                compact.position_inclusive(offset)?,
+                terminated.position_inclusive(offset)?,
+                "default implementation at {offset}"
+            );
+        }
+        let skipped = crate::ParseOptions::new()
+            .skip_bom(true)
+            .parse("\u{FEFF}")?;
+        let compact = crate::compact::CompactLines::try_from(&skipped)?;
+        assert_eq!(
+            compact.position_inclusive(3)?,
+            skipped.position_inclusive(3)?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! Snapping offsets to extended grapheme cluster boundaries, so carets never split a user-perceived
+//! character.
+//!
+//! Requires the `unicode-segmentation` feature.
+
+use unicode_segmentation::GraphemeCursor;
+
+use crate::Bias;
+
+/// Move an offset of `source` to the nearest grapheme cluster boundary in the direction of
+/// `bias`, leaving it alone if it is already on one.
+///
+/// Offsets inside a multi-byte character are handled too, and offsets past the end of `source`
+/// are clamped to it.
+///
+/// ```
+/// use line_position::{graphemes::snap_to_grapheme_boundary, Bias};
+///
+/// let source = "e\u{301}!"; // "é" written with a combining accent
+/// assert_eq!(snap_to_grapheme_boundary(source, 1, Bias::Backward), 0);
+/// assert_eq!(snap_to_grapheme_boundary(source, 1, Bias::Forward), 3);
+/// assert_eq!(snap_to_grapheme_boundary(source, 3, Bias::Backward), 3);
+/// ```
+pub fn snap_to_grapheme_boundary(source: &str, offset: usize, bias: Bias) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        match bias {
+            Bias::Backward => offset -= 1,
+            Bias::Forward => offset += 1,
+        }
+    }
+
+    // With the whole text as a single chunk, the cursor never needs more context.
+    let mut cursor = GraphemeCursor::new(offset, source.len(), true);
+    if cursor.is_boundary(source, 0).unwrap_or(true) {
+        return offset;
+    }
+    let snapped = match bias {
+        Bias::Backward => cursor.prev_boundary(source, 0),
+        Bias::Forward => cursor.next_boundary(source, 0),
+    };
+    snapped.ok().flatten().unwrap_or(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_to_grapheme_boundary() {
+        // A family emoji joined with zero width joiners, then a flag of two regional indicators.
+        let source = "a👨\u{200d}👩\u{200d}👧🇫🇷\r\nb";
+        let family = 1..19;
+        let flag = 19..27;
+
+        for offset in family.clone() {
+            assert_eq!(
+                super::snap_to_grapheme_boundary(source, offset, Bias::Backward),
+                match offset == family.start {
+                    true => offset,
+                    false => family.start,
+                }
+            );
+        }
+        assert_eq!(
+            super::snap_to_grapheme_boundary(source, 5, Bias::Forward),
+            family.end
+        );
+        assert_eq!(
+            super::snap_to_grapheme_boundary(source, 23, Bias::Forward),
+            flag.end
+        );
+        assert_eq!(
+            super::snap_to_grapheme_boundary(source, 28, Bias::Backward),
+            27,
+            "\\r\\n is one cluster"
+        );
+        assert_eq!(
+            super::snap_to_grapheme_boundary(source, 100, Bias::Backward),
+            source.len()
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Resolution of [grep-searcher](https://docs.rs/grep-searcher) matches into positions.
+//!
+//! Requires the `grep` feature.
+
+use std::io;
+use std::ops::Range;
+
+use grep_matcher::Matcher;
+use grep_searcher::{Searcher, Sink, SinkMatch};
+
+use crate::{LinePosition, Lines};
+
+/// A single match found by a [PositionSink].
+#[derive(Debug)]
+pub struct SearchMatch<'b> {
+    position: LinePosition,
+    range: Range<usize>,
+    line: &'b [u8],
+}
+
+impl<'b> SearchMatch<'b> {
+    /// Position of the start of the match.
+    pub fn position(&self) -> &LinePosition {
+        &self.position
+    }
+
+    /// Absolute byte range of the match in the searched input.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Text of the line the match starts on, including its terminator.
+    pub fn line(&self) -> &'b [u8] {
+        self.line
+    }
+}
+
+/// A [Sink] that finds every match within the lines reported by a [Searcher] and resolves it
+/// through a [Lines] index of the same input.
+///
+/// Since lines come from the index, the searcher can be built with line numbers turned off.
+///
+/// ```
+/// use grep_regex::RegexMatcher;
+/// use grep_searcher::SearcherBuilder;
+/// use line_position::{grep::PositionSink, Lines};
+///
+/// let input = "let a = 1;\nlet b = a;\n";
+/// let lines = Lines::parse(input);
+/// let matcher = RegexMatcher::new(r"\ba\b").unwrap();
+/// let mut found = Vec::new();
+/// let sink = PositionSink::new(&lines, &matcher, |found_match| {
+///     found.push((found_match.position().line(), found_match.position().offset()));
+///     Ok(true)
+/// });
+/// SearcherBuilder::new()
+///     .line_number(false)
+///     .build()
+///     .search_slice(&matcher, input.as_bytes(), sink)
+///     .unwrap();
+/// assert_eq!(found, [(1, 4), (2, 8)]);
+/// ```
+pub struct PositionSink<'a, M, F> {
+    lines: &'a Lines,
+    matcher: M,
+    matched: F,
+}
+
+impl<'a, M, F> PositionSink<'a, M, F>
+where
+    M: Matcher,
+    F: FnMut(SearchMatch<'_>) -> io::Result<bool>,
+{
+    /// Create a sink calling `matched` for each match of `matcher`, which should be the matcher
+    /// given to the searcher. Searching stops when `matched` returns `false` or an error.
+    pub fn new(lines: &'a Lines, matcher: M, matched: F) -> Self {
+        PositionSink {
+            lines,
+            matcher,
+            matched,
+        }
+    }
+}
+
+impl<M, F> Sink for PositionSink<'_, M, F>
+where
+    M: Matcher,
+    F: FnMut(SearchMatch<'_>) -> io::Result<bool>,
+{
+    type Error = io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, found: &SinkMatch<'_>) -> io::Result<bool> {
+        let base = usize::try_from(found.absolute_byte_offset()).map_err(io::Error::other)?;
+        let bytes = found.bytes();
+        let mut outcome = Ok(true);
+
+        self.matcher
+            .find_iter(bytes, |m| {
+                let range = base + m.start()..base + m.end();
+                outcome = self
+                    .lines
+                    .position(range.start)
+                    .map_err(io::Error::other)
+                    .and_then(|position| {
+                        let index = position.line() - 1;
+                        let line = self
+                            .lines
+                            .line(index)
+                            .and_then(|line| bytes.get(line.start - base..line.end - base))
+                            .unwrap_or_default();
+                        (self.matched)(SearchMatch {
+                            position,
+                            range,
+                            line,
+                        })
+                    });
+                matches!(outcome, Ok(true))
+            })
+            .map_err(|error| io::Error::other(error.to_string()))?;
+
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grep_regex::RegexMatcherBuilder;
+    use grep_searcher::SearcherBuilder;
+
+    fn search(input: &str, pattern: &str, multi_line: bool) -> Vec<(usize, usize, String)> {
+        let lines = Lines::parse(input);
+        let matcher = RegexMatcherBuilder::new()
+            .multi_line(multi_line)
+            .build(pattern)
+            .unwrap();
+        let mut found = Vec::new();
+        let sink = PositionSink::new(&lines, &matcher, |found_match| {
+            found.push((
+                found_match.position().line(),
+                found_match.position().offset(),
+                String::from_utf8_lossy(found_match.line()).into_owned(),
+            ));
+            Ok(true)
+        });
+        SearcherBuilder::new()
+            .line_number(false)
+            .multi_line(multi_line)
+            .build()
+            .search_slice(&matcher, input.as_bytes(), sink)
+            .unwrap();
+        found
+    }
+
+    #[test]
+    fn position_sink() {
+        assert_eq!(
+            search("foo\r\nbar foo foo\r\n", "foo", false),
+            [
+                (1, 0, "foo\r\n".to_string()),
+                (2, 4, "bar foo foo\r\n".to_string()),
+                (2, 8, "bar foo foo\r\n".to_string()),
+            ]
+        );
+        assert_eq!(
+            search("a\nb\nc", r"b\nc", true),
+            [(2, 0, "b\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn position_sink_stops() {
+        let input = "x x x";
+        let lines = Lines::parse(input);
+        let matcher = grep_regex::RegexMatcher::new("x").unwrap();
+        let mut calls = 0;
+        let sink = PositionSink::new(&lines, &matcher, |_| {
+            calls += 1;
+            Ok(calls < 2)
+        });
+        Searcher::new()
+            .search_slice(&matcher, input.as_bytes(), sink)
+            .unwrap();
+        assert_eq!(calls, 2);
+    }
+}
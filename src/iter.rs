@@ -0,0 +1,363 @@
+//! Iterators over the lines of an index.
+
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+use crate::{Lines, LinesError, Span};
+
+/// Owning iterator over the lines of a [Lines] index, created by its [IntoIterator] impl.
+///
+/// Yields the line number, starting with 1, and the byte range of the line including its
+/// terminator.
+///
+/// ```
+/// use line_position::Lines;
+///
+/// let lines = Lines::parse("abc\ndef\n");
+/// let spans: Vec<_> = lines.into_iter().collect();
+/// assert_eq!(spans, vec![(1, 0..4), (2, 4..8)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IntoIter {
+    lines: Lines,
+    remaining: Range<usize>,
+}
+
+impl IntoIter {
+    pub(crate) fn new(lines: Lines) -> Self {
+        IntoIter {
+            remaining: 0..lines.num_lines(),
+            lines,
+        }
+    }
+}
+
+/// Convert a zero-indexed line into the item type of line iterators.
+fn item(lines: &Lines, index: usize) -> Option<(usize, Range<usize>)> {
+    lines
+        .line(index)
+        .map(|line| (index + 1, line.start..line.end))
+}
+
+impl Iterator for IntoIter {
+    type Item = (usize, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        item(&self.lines, self.remaining.next()?)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        item(&self.lines, self.remaining.next_back()?)
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl FusedIterator for IntoIter {}
+
+impl IntoIterator for Lines {
+    type Item = (usize, Range<usize>);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter::new(self)
+    }
+}
+
+/// Borrowing iterator over the lines of a [Lines] index, created by [iter](Lines::iter).
+///
+/// Yields the same items as [IntoIter].
+///
+/// ```
+/// use line_position::Lines;
+///
+/// let lines = Lines::parse("abc\ndef\n");
+/// let last = lines.iter().next_back();
+/// assert_eq!(last, Some((2, 4..8)));
+/// for (number, span) in &lines {
+///     assert_eq!(span.len(), 4, "line {number}");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    lines: &'a Lines,
+    remaining: Range<usize>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = (usize, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        item(self.lines, self.remaining.next()?)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        item(self.lines, self.remaining.next_back()?)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a Lines {
+    type Item = (usize, Range<usize>);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the lines of a [Lines] index together with their text, created by
+/// [lines_with_text](Lines::lines_with_text).
+///
+/// Yields the line number, starting with 1, the byte range of the line including its
+/// terminator, and the text of that range.
+#[derive(Debug, Clone)]
+pub struct LinesWithText<'a> {
+    inner: Iter<'a>,
+    source: &'a str,
+}
+
+impl<'a> LinesWithText<'a> {
+    fn item(&self, (number, span): (usize, Range<usize>)) -> (usize, Range<usize>, &'a str) {
+        (number, span.clone(), &self.source[span])
+    }
+}
+
+impl<'a> Iterator for LinesWithText<'a> {
+    type Item = (usize, Range<usize>, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.inner.next()?;
+        Some(self.item(next))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for LinesWithText<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let next = self.inner.next_back()?;
+        Some(self.item(next))
+    }
+}
+
+impl ExactSizeIterator for LinesWithText<'_> {}
+
+impl FusedIterator for LinesWithText<'_> {}
+
+/// Iterator over the lines a span touches, created by [lines_in_span](Lines::lines_in_span).
+///
+/// Yields the line number, starting with 1, and the part of the span that falls on the line,
+/// which may include its terminator.
+#[derive(Debug, Clone)]
+pub struct LinesInSpan<'a> {
+    lines: &'a Lines,
+    remaining: Range<usize>,
+    span: Span,
+}
+
+impl LinesInSpan<'_> {
+    fn item(&self, index: usize) -> Option<(usize, Range<usize>)> {
+        let line = self.lines.line(index)?;
+        let start = line.start.max(self.span.start());
+        Some((index + 1, start..line.end.min(self.span.end())))
+    }
+}
+
+impl Iterator for LinesInSpan<'_> {
+    type Item = (usize, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.remaining.next()?;
+        self.item(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for LinesInSpan<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let index = self.remaining.next_back()?;
+        self.item(index)
+    }
+}
+
+impl ExactSizeIterator for LinesInSpan<'_> {}
+
+impl FusedIterator for LinesInSpan<'_> {}
+
+impl Lines {
+    /// Iterate over the lines with their line numbers, starting with 1, and byte ranges
+    /// including their terminators.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            lines: self,
+            remaining: 0..self.num_lines(),
+        }
+    }
+
+    /// Iterate over the lines of `source`, the text the index was built from, with their line
+    /// numbers and byte ranges.
+    ///
+    /// The index is checked against `source` before iterating, failing with
+    /// [StaleIndex](LinesError::StaleIndex) if they do not match, so the iterator itself cannot
+    /// fail or panic.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let source = "abc\ndé\n";
+    /// let lines = Lines::parse(source);
+    /// let items: Vec<_> = lines.lines_with_text(source).unwrap().collect();
+    /// assert_eq!(items, vec![(1, 0..4, "abc\n"), (2, 4..8, "dé\n")]);
+    /// ```
+    pub fn lines_with_text<'a>(&'a self, source: &'a str) -> Result<LinesWithText<'a>, LinesError> {
+        let matches = self.total_len() == source.len()
+            && self
+                .line_starts()
+                .iter()
+                .all(|&start| source.is_char_boundary(start));
+        match matches {
+            true => Ok(LinesWithText {
+                inner: self.iter(),
+                source,
+            }),
+            false => Err(LinesError::StaleIndex),
+        }
+    }
+
+    /// Iterate over the lines a span touches, with the part of the span on each, such as for
+    /// underlining a diagnostic that spans several lines.
+    ///
+    /// An empty span touches the line it is on, if any. Fails with
+    /// [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) like [range](Lines::range).
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("abc\ndef\nghi\n");
+    /// let parts: Vec<_> = lines.lines_in_span(2..9).unwrap().collect();
+    /// assert_eq!(parts, vec![(1, 2..4), (2, 4..8), (3, 8..9)]);
+    /// ```
+    pub fn lines_in_span(&self, span: impl Into<Span>) -> Result<LinesInSpan<'_>, LinesError> {
+        let span = span.into();
+        if span.end() > self.total_len() {
+            return Err(LinesError::OffsetOutOfBounds {
+                offset: span.end(),
+                max: self.total_len(),
+            });
+        }
+        if span.start() > span.end() {
+            return Err(LinesError::OffsetOutOfBounds {
+                offset: span.start(),
+                max: span.end(),
+            });
+        }
+        let first = self.line_index(span.start()).unwrap_or(self.num_lines());
+        let last = match span.is_empty() {
+            true => (first + 1).min(self.num_lines()),
+            false => self
+                .line_index(span.end() - 1)
+                .map_or(first, |index| index + 1),
+        };
+        Ok(LinesInSpan {
+            lines: self,
+            remaining: first..last,
+            span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_iter_composes() {
+        let lines = Lines::parse("a\nbc\ndef");
+        let iter = lines.clone().into_iter();
+        assert_eq!(iter.len(), 3, "exact size");
+
+        let reversed: Vec<_> = lines.clone().into_iter().rev().collect();
+        assert_eq!(reversed, vec![(3, 5..8), (2, 2..5), (1, 0..2)]);
+
+        let zipped: Vec<_> = lines.into_iter().zip(["x", "y"]).collect();
+        assert_eq!(zipped, vec![((1, 0..2), "x"), ((2, 2..5), "y")]);
+    }
+
+    #[test]
+    fn iter_matches_into_iter() {
+        let lines = Lines::parse("a\r\nbc\r\n\r\n");
+        let mut iter = lines.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some((1, 0..3)));
+        assert_eq!(iter.len(), 2);
+
+        let borrowed: Vec<_> = (&lines).into_iter().rev().collect();
+        let owned: Vec<_> = lines.into_iter().rev().collect();
+        assert_eq!(borrowed, owned);
+    }
+
+    #[test]
+    fn lines_with_text() -> Result<(), LinesError> {
+        let source = "a\r\nbc\r\ndef";
+        let lines = Lines::parse(source);
+
+        let items: Vec<_> = lines.lines_with_text(source)?.rev().collect();
+        assert_eq!(
+            items,
+            vec![(3, 7..10, "def"), (2, 3..7, "bc\r\n"), (1, 0..3, "a\r\n")]
+        );
+        assert_eq!(Lines::parse("").lines_with_text("")?.count(), 0);
+
+        assert!(matches!(
+            lines.lines_with_text("a\r\nbc"),
+            Err(LinesError::StaleIndex)
+        ));
+        assert!(matches!(
+            Lines::parse("é\nx").lines_with_text("abé"),
+            Err(LinesError::StaleIndex)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn lines_in_span() -> Result<(), LinesError> {
+        let lines = Lines::parse("ab\r\ncd\ne");
+        let parts = |span: Range<usize>| -> Result<Vec<_>, LinesError> {
+            Ok(lines.lines_in_span(span)?.collect())
+        };
+
+        assert_eq!(parts(1..7)?, vec![(1, 1..4), (2, 4..7)]);
+        assert_eq!(parts(4..7)?, vec![(2, 4..7)], "a whole line");
+        assert_eq!(parts(5..5)?, vec![(2, 5..5)], "an empty span");
+        assert_eq!(parts(8..8)?, vec![], "the end of the input");
+        assert_eq!(lines.lines_in_span(0..8)?.rev().len(), 3);
+        assert!(matches!(
+            lines.lines_in_span(3..9),
+            Err(LinesError::OffsetOutOfBounds { offset: 9, max: 8 })
+        ));
+
+        Ok(())
+    }
+}
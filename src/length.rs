@@ -0,0 +1,182 @@
+//! Measuring line lengths, for "maximum line length" lints.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+#[cfg(feature = "unicode-width")]
+use crate::width::{display_width, AmbiguousWidth};
+use crate::Lines;
+
+/// Unit in which to measure the length of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    /// UTF-8 bytes.
+    Bytes,
+    /// Unicode scalar values.
+    Chars,
+    /// Terminal cells, as in [display_width].
+    ///
+    /// Requires the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    DisplayWidth(AmbiguousWidth),
+}
+
+impl Measure {
+    /// Length of `text` in this unit.
+    pub fn len(self, text: &str) -> usize {
+        match self {
+            Measure::Bytes => text.len(),
+            Measure::Chars => text.chars().count(),
+            #[cfg(feature = "unicode-width")]
+            Measure::DisplayWidth(ambiguous) => display_width(text, ambiguous),
+        }
+    }
+}
+
+/// A line longer than the limit given to [lines_exceeding](Lines::lines_exceeding).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongLine {
+    line: usize,
+    len: usize,
+    overflow: Range<usize>,
+}
+
+impl LongLine {
+    /// Line number, starting with 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Length of the line content, excluding its terminator, in the requested unit.
+    pub fn length(&self) -> usize {
+        self.len
+    }
+
+    /// Byte range of the content past the limit, from the first character that does not fit
+    /// to the end of the line content.
+    pub fn overflow(&self) -> Range<usize> {
+        self.overflow.clone()
+    }
+}
+
+impl Lines {
+    /// Find the lines of `source`, the text this index was built from, whose content is longer
+    /// than `limit` in the given unit.
+    ///
+    /// ```
+    /// use line_position::{length::Measure, Lines};
+    ///
+    /// let source = "short\nwäy too long\n";
+    /// let lines = Lines::parse(source);
+    /// let long = lines.lines_exceeding(source, 6, Measure::Chars);
+    /// assert_eq!(long.len(), 1);
+    /// assert_eq!((long[0].line(), long[0].length()), (2, 12));
+    /// assert_eq!(&source[long[0].overflow()], "o long");
+    /// ```
+    pub fn lines_exceeding(&self, source: &str, limit: usize, measure: Measure) -> Vec<LongLine> {
+        self.lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let content = source.get(line.start..line.content_end)?;
+                let len = measure.len(content);
+                if len <= limit {
+                    return None;
+                }
+                let mut used = 0;
+                let tail = content
+                    .char_indices()
+                    .find(|&(at, c)| {
+                        used += measure.len(&content[at..at + c.len_utf8()]);
+                        used > limit
+                    })
+                    .map_or(content.len(), |(at, _)| at);
+                Some(LongLine {
+                    line: index + 1,
+                    len,
+                    overflow: line.start + tail..line.content_end,
+                })
+            })
+            .collect()
+    }
+
+    /// Line number, starting with 1, and content length in bytes of the longest line, or [None]
+    /// for empty input.
+    ///
+    /// Ties go to the first of the lines. Terminators are not counted, and the lengths come from
+    /// the index alone, so other units need [lines_exceeding](Lines::lines_exceeding) and the
+    /// text.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("ab\r\nabcd\n\nabc");
+    /// assert_eq!(lines.longest_line(), Some((2, 4)));
+    /// assert_eq!(lines.shortest_nonempty_line(), Some((1, 2)));
+    /// ```
+    pub fn longest_line(&self) -> Option<(usize, usize)> {
+        self.content_lens().rev().max_by_key(|&(_, len)| len)
+    }
+
+    /// Line number and content length in bytes of the shortest line with content, or [None] if
+    /// no line has any, like [longest_line](Lines::longest_line).
+    pub fn shortest_nonempty_line(&self) -> Option<(usize, usize)> {
+        self.content_lens()
+            .filter(|&(_, len)| len > 0)
+            .min_by_key(|&(_, len)| len)
+    }
+
+    /// Line numbers with the content length in bytes of each line.
+    fn content_lens(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + '_ {
+        (0..self.num_lines()).filter_map(|index| {
+            let line = self.line(index)?;
+            Some((index + 1, line.content_end - self.column_start(index)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exceeding(source: &str, limit: usize, measure: Measure) -> Vec<(usize, usize, &str)> {
+        Lines::parse(source)
+            .lines_exceeding(source, limit, measure)
+            .into_iter()
+            .map(|long| (long.line(), long.length(), &source[long.overflow()]))
+            .collect()
+    }
+
+    #[test]
+    fn lines_exceeding() {
+        let source = "abcd\r\néé\r\nabc\r\n";
+
+        assert_eq!(
+            exceeding(source, 3, Measure::Bytes),
+            [(1, 4, "d"), (2, 4, "é")]
+        );
+        assert_eq!(exceeding(source, 3, Measure::Chars), [(1, 4, "d")]);
+        assert_eq!(exceeding(source, 4, Measure::Bytes), []);
+        assert_eq!(exceeding("日本", 1, Measure::Bytes), [(1, 6, "日本")]);
+    }
+
+    #[test]
+    fn extremes() {
+        let lines = Lines::parse("abc\r\n\nab\nxyz\nde");
+        assert_eq!(lines.longest_line(), Some((1, 3)), "first of the longest");
+        assert_eq!(lines.shortest_nonempty_line(), Some((3, 2)));
+
+        let blank = Lines::parse("\n\r\n");
+        assert_eq!(blank.longest_line(), Some((1, 0)));
+        assert_eq!(blank.shortest_nonempty_line(), None);
+        assert_eq!(Lines::parse("").longest_line(), None);
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn lines_exceeding_display_width() {
+        let measure = Measure::DisplayWidth(AmbiguousWidth::Narrow);
+
+        assert_eq!(exceeding("日本語\nabc", 3, measure), [(1, 6, "本語")]);
+        assert_eq!(exceeding("日本語\nabc", 4, measure), [(1, 6, "語")]);
+    }
+}
@@ -27,28 +27,167 @@
 //!   different design
 //! * [line-span](https://crates.io/crates/line-span) offers more data with a more complex API
 //!
+//! # `no_std`
+//!
+//! Without the default `std` feature, the crate is `no_std` and only needs `alloc`. Reading
+//! files, [documents](document) and [workspaces](workspace) then go away, as do the features
+//! that depend on them.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
+// Denied rather than forbidden, so the `unchecked` and `mmap` modules alone can opt in.
+#![deny(unsafe_code)]
+
+extern crate alloc;
+
+use alloc::{collections::TryReserveError, format, string::String, vec, vec::Vec};
+use core::{
+    fmt,
+    ops::{Range, RangeInclusive},
+};
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
 
 use thiserror::Error;
 
+#[cfg(feature = "rkyv")]
+pub mod archive;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "unicode-bidi")]
+mod bidi;
+pub mod bytes;
+#[cfg(feature = "codespan-reporting")]
+mod codespan;
+pub mod columns;
+pub mod compact;
+#[cfg(feature = "compat")]
+pub mod compat;
+pub mod cursor;
+#[cfg(feature = "diff")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod document;
+mod encoding;
+pub mod folding;
+pub mod format;
+#[cfg(feature = "unicode-segmentation")]
+pub mod graphemes;
+#[cfg(feature = "grep")]
+pub mod grep;
+pub mod iter;
+pub mod length;
+pub mod locate;
+#[cfg(feature = "lsp-types")]
+pub mod lsp;
+#[doc(hidden)]
+pub mod macros;
+pub mod markers;
+#[cfg(feature = "miette")]
+mod miette_source;
+#[cfg(feature = "memmap2")]
+#[allow(unsafe_code)]
+mod mmap;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "serde_json")]
+pub mod sarif;
+pub mod source;
+#[allow(unsafe_code)]
+mod unchecked;
+#[cfg(feature = "unicode-width")]
+pub mod width;
+#[cfg(feature = "std")]
+pub mod workspace;
+
 /// Error type for this crate.
+///
+/// It implements [Error](std::error::Error), so it converts into error types like
+/// `Box<dyn Error>` with `?`. New variants may be added without a breaking change.
+///
+/// With the `serde` feature, errors can be serialized, except for
+/// [AllocationFailed](LinesError::AllocationFailed), whose cause cannot.
 #[derive(Debug, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum LinesError {
-    /// The offset passed to [position][Lines::position] was beyond the length of the input.
-    #[error("offset outside of input bounds")]
-    OffsetOutOfBounds,
+    /// An offset, such as that passed to [position][Lines::position], was beyond the length of
+    /// the input or outside of the range it had to fall in.
+    #[error("offset {offset} outside of input bounds of {max}")]
+    OffsetOutOfBounds {
+        /// The requested offset.
+        offset: usize,
+        /// End of the valid range, usually the length of the input.
+        max: usize,
+    },
+    /// A line number was beyond the number of lines in the input.
+    #[error("line outside of input bounds")]
+    LineOutOfBounds,
+    /// A column was beyond the end of its line.
+    #[error("column outside of line bounds")]
+    ColumnOutOfBounds,
+    /// An offset fell inside a multi-byte UTF-8 sequence, where a column is meaningless.
+    #[error("offset {offset} is not on a character boundary")]
+    NotCharBoundary {
+        /// The requested offset.
+        offset: usize,
+    },
+    /// A file identifier did not refer to a file in the [workspace](workspace::Workspace).
+    #[error("unknown file")]
+    UnknownFile,
+    /// The text passed to [validate](Lines::validate) is not the text the index was built from.
+    #[error("index was built for different text")]
+    StaleIndex,
+    /// Memory for the index could not be allocated, as reported by
+    /// [try_parse](Lines::try_parse).
+    #[error("failed to allocate memory for the index")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    AllocationFailed(#[from] TryReserveError),
+    /// The input was longer than allowed by [ParseOptions::max_len].
+    #[error("input of {len} bytes exceeds the limit of {max} bytes")]
+    InputTooLong {
+        /// Length of the input in bytes.
+        len: usize,
+        /// Maximum allowed length in bytes.
+        max: usize,
+    },
+    /// The input had more lines than allowed by [ParseOptions::max_lines].
+    #[error("input exceeds the limit of {max} lines")]
+    TooManyLines {
+        /// Maximum allowed number of lines.
+        max: usize,
+    },
+    /// A line start passed to [from_line_starts](Lines::from_line_starts) did not follow the
+    /// previous one within the input.
+    #[error("line {line} does not start after the previous line within the input")]
+    InvalidLineStart {
+        /// Line number of the offending start, starting with 1.
+        line: usize,
+    },
 }
 
 type LinesResult = Result<LinePosition, LinesError>;
 
 /// Position within the file.
-#[derive(Debug)]
+///
+/// Positions are ordered by line, then by offset within the line, so diagnostics can be sorted by
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LinePosition {
     line: usize,
     offset: usize,
 }
 
 impl LinePosition {
+    /// Create a position from a line number, starting with 1, and an offset within the line,
+    /// starting with 0.
+    ///
+    /// This is mainly useful for implementing [LineIndex] and in tests. Positions can also be
+    /// converted from and into `(line, offset)` tuples.
+    pub fn new(line: usize, offset: usize) -> Self {
+        LinePosition { line, offset }
+    }
+
     /// Line number of position, starting with 1.
     pub fn line(&self) -> usize {
         self.line
@@ -58,14 +197,166 @@ impl LinePosition {
     pub fn offset(&self) -> usize {
         self.offset
     }
+
+    /// Display the position as `path:line:column`, like its [Display](fmt::Display) output
+    /// prefixed with a path.
+    ///
+    /// ```
+    /// use line_position::LinePosition;
+    ///
+    /// let position = LinePosition::new(2, 8);
+    /// assert_eq!(position.with_path("src/main.rs").to_string(), "src/main.rs:2:9");
+    /// ```
+    pub fn with_path(self, path: &str) -> format::PathPosition<'_> {
+        format::PathPosition {
+            path,
+            position: self,
+        }
+    }
 }
 
+/// Displays as `line:column`, with the column starting with 1 as compilers and editors show it.
+///
+/// ```
+/// use line_position::LinePosition;
+///
+/// assert_eq!(LinePosition::new(2, 8).to_string(), "2:9");
+/// ```
+impl fmt::Display for LinePosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.offset + 1)
+    }
+}
+
+/// Range between a start and an end position, such as that of a diagnostic.
+///
+/// With the `lsp-types` feature, ranges convert to and from LSP ranges.
 #[derive(Debug)]
-struct Line {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionRange {
+    start: LinePosition,
+    end: LinePosition,
+}
+
+impl PositionRange {
+    /// Create a range from its start and end.
+    pub fn new(start: LinePosition, end: LinePosition) -> Self {
+        PositionRange { start, end }
+    }
+
+    /// Start of the range.
+    pub fn start(&self) -> &LinePosition {
+        &self.start
+    }
+
+    /// End of the range.
+    pub fn end(&self) -> &LinePosition {
+        &self.end
+    }
+}
+
+/// Byte range of the input, such as the span of a token or diagnostic.
+///
+/// Spans convert to and from [Range]s, and are [resolved](Lines::range) into positions in one
+/// call.
+///
+/// ```
+/// use line_position::Span;
+///
+/// let span = Span::from(2..5);
+/// assert!(span.contains(4) && !span.contains(5));
+/// assert!(span.overlaps(Span::new(4, 8)));
+/// assert!(!span.overlaps(Span::new(5, 8)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
     start: usize,
     end: usize,
 }
 
+impl Span {
+    /// Create a span from its start and its exclusive end.
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Offset of the first byte of the span.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Offset just past the last byte of the span.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Length of the span in bytes, which is 0 for a reversed span.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Whether the span covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the span covers the byte at `offset`.
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+
+    /// Whether the spans cover at least one byte in common, so empty spans overlap nothing.
+    pub fn overlaps(&self, other: Span) -> bool {
+        !self.is_empty() && !other.is_empty() && self.start < other.end && other.start < self.end
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span::new(range.start, range.end)
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.start..span.end
+    }
+}
+
+/// Destructure a position into `(line, offset)`.
+impl From<LinePosition> for (usize, usize) {
+    fn from(position: LinePosition) -> Self {
+        (position.line, position.offset)
+    }
+}
+
+/// Construct a position from `(line, offset)`, failing with
+/// [LineOutOfBounds](LinesError::LineOutOfBounds) for line 0 since lines start with 1.
+impl TryFrom<(usize, usize)> for LinePosition {
+    type Error = LinesError;
+
+    fn try_from((line, offset): (usize, usize)) -> Result<Self, Self::Error> {
+        match line {
+            0 => Err(LinesError::LineOutOfBounds),
+            _ => Ok(LinePosition::new(line, offset)),
+        }
+    }
+}
+
+/// Bounds and terminator of a line, computed from the line starts of [Lines].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Line {
+    /// Offset of the first byte of the line.
+    pub(crate) start: usize,
+    /// Offset just past the line content, before any terminator.
+    pub(crate) content_end: usize,
+    /// Offset just past the line terminator.
+    pub(crate) end: usize,
+    /// Kind of the line terminator, if the line has one.
+    pub(crate) ending: Option<LineEnding>,
+}
+
 /// Parser for string data that exposes methods for querying offsets.
 ///
 /// Simply:
@@ -74,32 +365,168 @@ struct Line {
 /// 3. Use [line][LinePosition::line] to access the line number and [offset][LinePosition::offset] to access the line offset.
 ///
 /// See the [main page](crate) for a full example.
-#[derive(Debug)]
+///
+/// Indexes can be cloned to snapshot them alongside a version of the text, and compare equal when
+/// built from the same text with the same options. The default index is that of empty text.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Lines {
-    lines: Vec<Line>,
+    /// Offset of the first byte of each line. A line ends where the next one starts, or at the
+    /// end of the input.
+    starts: Vec<usize>,
+    /// Terminator of each line, one shorter than `starts` if the last line has none.
+    endings: Vec<LineEnding>,
+    /// Length in bytes of the parsed input.
+    len: usize,
+    /// Whether the input starts with a UTF-8 byte order mark.
+    bom: bool,
+    /// Whether columns on line 1 are counted from after the byte order mark.
+    skip_bom: bool,
+    /// Line terminators recognized when parsing, kept so edits are rescanned the same way.
+    breaks: Breaks,
 }
 
+/// UTF-8 encoding of the byte order mark U+FEFF.
+pub(crate) const BOM: &[u8] = b"\xef\xbb\xbf";
+
 impl Lines {
     /// Parse the given input string, storing the line data in the returned value.
     ///
-    /// The parser assumes line endings are consistent, i.e. all `\n` or all `\r\n`.
-    /// As a consequence, if the input contains an `\r\n`, that is the delimiter used.
+    /// Every `\n` ends a line, together with the `\r` before it if there is one, so files that
+    /// mix `\n` and `\r\n` line endings are split on each of them.
     pub fn parse(input: &str) -> Self {
-        let mut lines = Vec::new();
-        let line_ending = match input.contains("\r\n") {
-            true => "\r\n",
-            false => "\n",
-        };
+        Lines::scan(input.as_bytes())
+    }
 
+    /// Index the line terminators of arbitrary bytes, following the rules of [parse](Lines::parse).
+    pub(crate) fn scan(input: &[u8]) -> Self {
+        Lines::scan_into(input, Breaks::default(), Lines::default(), usize::MAX)
+    }
+
+    /// Parse input like [parse](Lines::parse), but fail with
+    /// [AllocationFailed](LinesError::AllocationFailed) instead of aborting when memory for the
+    /// index cannot be allocated.
+    ///
+    /// The index is allocated up front for the worst case of one line per `\n`, so it needs one
+    /// extra pass over the input.
+    pub fn try_parse(input: &str) -> Result<Self, LinesError> {
+        let input = input.as_bytes();
+        let terminators = memchr::memchr_iter(b'\n', input).count();
+        let mut lines = Lines::default();
+        lines.starts.try_reserve_exact(terminators + 1)?;
+        lines.endings.try_reserve_exact(terminators)?;
+        Ok(Lines::scan_into(
+            input,
+            Breaks::default(),
+            lines,
+            usize::MAX,
+        ))
+    }
+
+    /// Index line terminators into `lines`, which is expected to be empty, stopping early once
+    /// there are more than `max_lines`.
+    fn scan_into(input: &[u8], breaks: Breaks, lines: Lines, max_lines: usize) -> Self {
+        // Unless Unicode breaks are enabled, every terminator ends in `\n` or `\r`, so only those
+        // bytes need to be looked at and a vectorized search can skip over the rest.
+        match (breaks.lone_cr, breaks.unicode) {
+            (false, false) => {
+                let candidates = memchr::memchr_iter(b'\n', input);
+                Lines::scan_candidates(input, breaks, candidates, lines, max_lines)
+            }
+            (true, false) => {
+                let candidates = memchr::memchr2_iter(b'\n', b'\r', input);
+                Lines::scan_candidates(input, breaks, candidates, lines, max_lines)
+            }
+            (_, true) => Lines::scan_candidates(input, breaks, 0..input.len(), lines, max_lines),
+        }
+    }
+
+    /// Index line terminators like [scan_into](Lines::scan_into), looking only at the bytes at
+    /// `candidates`, which must include the last byte of every terminator in order.
+    fn scan_candidates(
+        input: &[u8],
+        breaks: Breaks,
+        candidates: impl Iterator<Item = usize>,
+        mut lines: Lines,
+        max_lines: usize,
+    ) -> Self {
         let mut start: usize = 0;
-        for input_line in input.split_inclusive(line_ending) {
-            let end = start + input_line.len();
-            let line = Line { start, end };
-            start = end;
-            lines.push(line);
+        for index in candidates {
+            let byte = input[index];
+            let preceded_by = |prefix: &[u8]| input[..index].ends_with(prefix);
+            let ending = match byte {
+                b'\n' if preceded_by(b"\r") => LineEnding::Crlf,
+                b'\n' => LineEnding::Lf,
+                b'\r' if breaks.lone_cr && input.get(index + 1) != Some(&b'\n') => LineEnding::Cr,
+                0x0c if breaks.unicode => LineEnding::FormFeed,
+                0x0b if breaks.unicode => LineEnding::VerticalTab,
+                0x85 if breaks.unicode && preceded_by(&[0xc2]) => LineEnding::NextLine,
+                0xa8 if breaks.unicode && preceded_by(&[0xe2, 0x80]) => LineEnding::LineSeparator,
+                0xa9 if breaks.unicode && preceded_by(&[0xe2, 0x80]) => {
+                    LineEnding::ParagraphSeparator
+                }
+                _ => continue,
+            };
+            lines.starts.push(start);
+            lines.endings.push(ending);
+            start = index + 1;
+            if lines.starts.len() > max_lines {
+                break;
+            }
+        }
+        if start < input.len() && lines.starts.len() <= max_lines {
+            lines.starts.push(start);
         }
 
-        Lines { lines }
+        lines.len = input.len();
+        lines.bom = input.starts_with(BOM);
+        lines.breaks = breaks;
+        lines
+    }
+
+    /// Read the file at the given path and parse it, returning both the contents and the index.
+    ///
+    /// Fails if the file cannot be read or is not valid UTF-8.
+    #[cfg(feature = "std")]
+    pub fn parse_file(path: impl AsRef<Path>) -> io::Result<(String, Self)> {
+        let contents = fs::read_to_string(path)?;
+        let lines = Lines::parse(&contents);
+        Ok((contents, lines))
+    }
+
+    /// Index text read from `reader` without keeping the text, following the rules of
+    /// [parse](Lines::parse).
+    ///
+    /// Only the index is held in memory, so this suits very large files when only offsets are
+    /// needed. A `\r\n` split across reads is recognized. The input does not need to be valid
+    /// UTF-8.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::from_reader(&b"abc\r\ndef\r\n"[..]).unwrap();
+    /// assert_eq!(lines.num_lines(), 2);
+    /// assert_eq!(lines.position(6).unwrap().line(), 2);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_reader(mut reader: impl io::BufRead) -> io::Result<Self> {
+        let mut scanner = Scanner::default();
+        loop {
+            let buffer = match reader.fill_buf() {
+                Ok([]) => break,
+                Ok(buffer) => buffer,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            scanner.feed(buffer);
+            let len = buffer.len();
+            reader.consume(len);
+        }
+        Ok(scanner.finish())
     }
 
     /// Lookup the line number for a given character offset within the parsed string.
@@ -108,17 +535,378 @@ impl Lines {
     ///
     /// The only possible error here is [OffsetOutOfBounds](LinesError::OffsetOutOfBounds), which occurs if the offset is beyond the length of the input.
     pub fn position(&self, input_offset: usize) -> LinesResult {
-        let mut line_number = 1usize;
-        for line in &self.lines {
-            if input_offset >= line.start && input_offset < line.end {
-                return Ok(LinePosition {
-                    line: line_number,
-                    offset: input_offset - line.start,
-                });
+        let index = self
+            .line_index(input_offset)
+            .ok_or(LinesError::OffsetOutOfBounds {
+                offset: input_offset,
+                max: self.total_len(),
+            })?;
+        Ok(LinePosition {
+            line: index + 1,
+            offset: input_offset.saturating_sub(self.column_start(index)),
+        })
+    }
+
+    /// Lookup the position of an offset like [position](Lines::position), also accepting the
+    /// length of the input, such as a cursor at the end of the file.
+    ///
+    /// The end of the input is just past the content of the last line, or at column 0 of the
+    /// line after it if the input ends with a terminator, as the Language Server Protocol has it.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let position = Lines::parse("abc\ndef").position_inclusive(7).unwrap();
+    /// assert_eq!((position.line(), position.offset()), (2, 3));
+    /// let position = Lines::parse("abc\n").position_inclusive(4).unwrap();
+    /// assert_eq!((position.line(), position.offset()), (2, 0));
+    /// ```
+    pub fn position_inclusive(&self, input_offset: usize) -> LinesResult {
+        let count = self.num_lines();
+        match self.line(count.wrapping_sub(1)) {
+            Some(last) if input_offset == last.end => match last.ending {
+                Some(_) => Ok(LinePosition::new(count + 1, 0)),
+                None => Ok(LinePosition::new(
+                    count,
+                    input_offset.saturating_sub(self.column_start(count - 1)),
+                )),
+            },
+            None if input_offset == 0 => Ok(LinePosition::new(1, 0)),
+            _ => self.position(input_offset),
+        }
+    }
+
+    /// Lookup the position of an offset like [position_inclusive](Lines::position_inclusive),
+    /// clamping offsets past the end of the input to the end instead of failing.
+    ///
+    /// This suits offsets that may be stale, such as those an editor sends during rapid edits.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("abc\ndef");
+    /// let position = lines.position_clamped(100);
+    /// assert_eq!((position.line(), position.offset()), (2, 3));
+    /// ```
+    pub fn position_clamped(&self, input_offset: usize) -> LinePosition {
+        self.position_inclusive(input_offset.min(self.total_len()))
+            .expect("the end of the input is a valid position")
+    }
+
+    /// Lookup the position of an offset like [position](Lines::position), also checking that it
+    /// lies on a character boundary of `source`, the text the index was built from.
+    ///
+    /// Fails with [NotCharBoundary](LinesError::NotCharBoundary) for an offset inside a
+    /// multi-byte character, which usually indicates a bug in the caller's span arithmetic.
+    ///
+    /// ```
+    /// use line_position::{Lines, LinesError};
+    ///
+    /// let source = "é\n";
+    /// let lines = Lines::parse(source);
+    /// assert_eq!(lines.position_checked(source, 2).unwrap().offset(), 2);
+    /// assert!(matches!(
+    ///     lines.position_checked(source, 1),
+    ///     Err(LinesError::NotCharBoundary { offset: 1 })
+    /// ));
+    /// ```
+    pub fn position_checked(&self, source: &str, input_offset: usize) -> LinesResult {
+        let position = self.position(input_offset)?;
+        match source.is_char_boundary(input_offset) {
+            true => Ok(position),
+            false => Err(LinesError::NotCharBoundary {
+                offset: input_offset,
+            }),
+        }
+    }
+
+    /// Lookup the positions of both ends of a span, taking the end like
+    /// [position_inclusive](Lines::position_inclusive) so a span may reach the end of the input.
+    ///
+    /// Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if either end is out of
+    /// bounds or the span is reversed, reporting its start and end as in other range checks.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("abc\ndef");
+    /// let (start, end) = lines.range(2..6).unwrap();
+    /// assert_eq!((start.line(), start.offset()), (1, 2));
+    /// assert_eq!((end.line(), end.offset()), (2, 2));
+    /// ```
+    pub fn range(&self, span: impl Into<Span>) -> Result<(LinePosition, LinePosition), LinesError> {
+        let span = span.into();
+        let end = self.position_inclusive(span.end)?;
+        if span.start > span.end {
+            return Err(LinesError::OffsetOutOfBounds {
+                offset: span.start,
+                max: span.end,
+            });
+        }
+        Ok((self.position_inclusive(span.start)?, end))
+    }
+
+    /// Lookup only the line number, starting with 1, for a given offset within the parsed string.
+    ///
+    /// This skips computing the offset within the line, for hot loops that only need the line.
+    /// Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) like [position](Lines::position).
+    pub fn line_of(&self, input_offset: usize) -> Result<usize, LinesError> {
+        self.line_index(input_offset)
+            .map(|index| index + 1)
+            .ok_or(LinesError::OffsetOutOfBounds {
+                offset: input_offset,
+                max: self.total_len(),
+            })
+    }
+
+    /// Lookup only the line number for an offset the caller knows to be within the input.
+    ///
+    /// No bounds check is made and no error is constructed. Offsets beyond the input yield the
+    /// last line, and an empty input yields line 1, so the result is meaningless for them.
+    pub fn line_of_unchecked(&self, input_offset: usize) -> usize {
+        self.line_index(input_offset)
+            .unwrap_or(self.num_lines().saturating_sub(1))
+            + 1
+    }
+
+    /// Lookup only the offset within the line for an offset on a line number the caller already
+    /// knows, avoiding a second search.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist, or
+    /// with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if the offset is not on that line.
+    pub fn column_of(&self, line: usize, input_offset: usize) -> Result<usize, LinesError> {
+        let index = line.checked_sub(1).ok_or(LinesError::LineOutOfBounds)?;
+        let line = self.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        match input_offset >= line.start && input_offset < line.end {
+            true => Ok(input_offset.saturating_sub(self.column_start(index))),
+            false => Err(LinesError::OffsetOutOfBounds {
+                offset: input_offset,
+                max: line.end,
+            }),
+        }
+    }
+
+    /// Lookup the offset of a position given as a line number, starting with 1, and a column,
+    /// starting with 0, the inverse of [position](Lines::position).
+    ///
+    /// The column may point just past the line content, at its terminator. Fails with
+    /// [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist, or with
+    /// [ColumnOutOfBounds](LinesError::ColumnOutOfBounds) if the column is past the end of the
+    /// line content.
+    ///
+    /// ```
+    /// use line_position::{Lines, LinesError};
+    ///
+    /// let lines = Lines::parse("abc\r\ndef\r\n");
+    /// assert_eq!(lines.offset_of(2, 1).unwrap(), 6);
+    /// assert_eq!(lines.offset_of(2, 3).unwrap(), 8);
+    /// assert!(matches!(lines.offset_of(2, 4), Err(LinesError::ColumnOutOfBounds)));
+    /// assert!(matches!(lines.offset_of(3, 0), Err(LinesError::LineOutOfBounds)));
+    /// ```
+    pub fn offset_of(&self, line: usize, column: usize) -> Result<usize, LinesError> {
+        let index = line.checked_sub(1).ok_or(LinesError::LineOutOfBounds)?;
+        let line = self.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        let start = self.column_start(index);
+        match column <= line.content_end.saturating_sub(start) {
+            true => Ok(start + column),
+            false => Err(LinesError::ColumnOutOfBounds),
+        }
+    }
+
+    /// Byte range of a line, starting with 1, including its terminator.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("abc\r\ndef\r\n");
+    /// assert_eq!(lines.line_span(2).unwrap(), 5..10);
+    /// assert_eq!(lines.line_content_span(2).unwrap(), 5..8);
+    /// ```
+    pub fn line_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+        line.checked_sub(1)
+            .and_then(|index| self.line(index))
+            .map(|line| line.start..line.end)
+            .ok_or(LinesError::LineOutOfBounds)
+    }
+
+    /// Byte range of a line, starting with 1, without its terminator.
+    ///
+    /// Fails as [line_span](Lines::line_span) does.
+    pub fn line_content_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+        line.checked_sub(1)
+            .and_then(|index| self.line(index))
+            .map(|line| line.start..line.content_end)
+            .ok_or(LinesError::LineOutOfBounds)
+    }
+
+    /// Length in bytes of the content of a line, starting with 1, without its terminator.
+    ///
+    /// This is the largest column [offset_of](Lines::offset_of) accepts on the line, so it can
+    /// clamp columns sent by clients. Columns in other units are clamped with
+    /// [line_len_utf16](Lines::line_len_utf16) and [line_len_chars](Lines::line_len_chars).
+    /// Fails as [line_span](Lines::line_span) does.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("abc\r\nd");
+    /// assert_eq!(lines.line_len(1).unwrap(), 3);
+    /// assert_eq!(lines.line_len(2).unwrap(), 1);
+    /// ```
+    pub fn line_len(&self, line: usize) -> Result<usize, LinesError> {
+        let index = line.checked_sub(1).ok_or(LinesError::LineOutOfBounds)?;
+        let line = self.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        Ok(line.content_end.saturating_sub(self.column_start(index)))
+    }
+
+    /// Terminator of a line, starting with 1, or `None` for a last line without one.
+    ///
+    /// Fails as [line_span](Lines::line_span) does.
+    ///
+    /// ```
+    /// use line_position::{LineEnding, Lines};
+    ///
+    /// let lines = Lines::parse("abc\r\ndef\nghi");
+    /// assert_eq!(lines.line_ending_of(1).unwrap(), Some(LineEnding::Crlf));
+    /// assert_eq!(lines.line_ending_of(2).unwrap(), Some(LineEnding::Lf));
+    /// assert_eq!(lines.line_ending_of(3).unwrap(), None);
+    /// ```
+    pub fn line_ending_of(&self, line: usize) -> Result<Option<LineEnding>, LinesError> {
+        line.checked_sub(1)
+            .and_then(|index| self.line(index))
+            .map(|line| line.ending)
+            .ok_or(LinesError::LineOutOfBounds)
+    }
+
+    /// Whether the input starts with a UTF-8 byte order mark.
+    ///
+    /// The byte order mark is part of line 1, so its columns are shifted by three bytes relative
+    /// to what editors show, unless the index was parsed with
+    /// [skip_bom](ParseOptions::skip_bom).
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("\u{FEFF}abc");
+    /// assert!(lines.has_bom());
+    /// assert_eq!(lines.position(3).unwrap().offset(), 3);
+    /// ```
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Whether the input ended with a line terminator, which is false for empty input.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// assert!(Lines::parse("abc\n").has_trailing_newline());
+    /// assert!(!Lines::parse("abc\ndef").has_trailing_newline());
+    /// ```
+    pub fn has_trailing_newline(&self) -> bool {
+        !self.starts.is_empty() && self.endings.len() == self.starts.len()
+    }
+
+    /// The most common line terminator, or `None` if no line is terminated.
+    ///
+    /// Ties go to the terminator that occurs first, so this is what a formatter should use for
+    /// new lines to match the rest of the text.
+    pub fn line_ending(&self) -> Option<LineEnding> {
+        let mut counts: Vec<(LineEnding, usize)> = Vec::new();
+        for &ending in &self.endings {
+            match counts.iter_mut().find(|(seen, _)| *seen == ending) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((ending, 1)),
             }
-            line_number += 1
         }
-        Err(LinesError::OffsetOutOfBounds)
+        counts
+            .iter()
+            .rev()
+            .max_by_key(|&&(_, count)| count)
+            .map(|&(ending, _)| ending)
+    }
+
+    /// Slice the text of a range of line numbers out of `source`, the text this index was built
+    /// from, including the terminator of the last line.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the range is empty or any of
+    /// its lines does not exist, or with [StaleIndex](LinesError::StaleIndex) if `source` is too
+    /// short for the index.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let source = "a\nb\nc\nd\n";
+    /// let lines = Lines::parse(source);
+    /// assert_eq!(lines.text_of_lines(source, 2..=3).unwrap(), "b\nc\n");
+    /// assert_eq!(lines.content_of_lines(source, 2..=3).unwrap(), "b\nc");
+    /// ```
+    pub fn text_of_lines<'a>(
+        &self,
+        source: &'a str,
+        lines: RangeInclusive<usize>,
+    ) -> Result<&'a str, LinesError> {
+        let range = self.span_of_lines(lines, true)?;
+        source.get(range).ok_or(LinesError::StaleIndex)
+    }
+
+    /// Slice the text of a range of line numbers out of `source`, like
+    /// [text_of_lines](Lines::text_of_lines), but without the terminator of the last line.
+    pub fn content_of_lines<'a>(
+        &self,
+        source: &'a str,
+        lines: RangeInclusive<usize>,
+    ) -> Result<&'a str, LinesError> {
+        let range = self.span_of_lines(lines, false)?;
+        source.get(range).ok_or(LinesError::StaleIndex)
+    }
+
+    /// Byte range covering a range of line numbers, including the terminator of the last line,
+    /// as sliced by [text_of_lines](Lines::text_of_lines).
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the range is empty or any of
+    /// its lines does not exist.
+    pub fn byte_range_of_lines(
+        &self,
+        lines: RangeInclusive<usize>,
+    ) -> Result<Range<usize>, LinesError> {
+        self.span_of_lines(lines, true)
+    }
+
+    /// Byte range covering a range of line numbers, optionally with the last terminator.
+    fn span_of_lines(
+        &self,
+        lines: RangeInclusive<usize>,
+        terminator: bool,
+    ) -> Result<Range<usize>, LinesError> {
+        let first = lines
+            .start()
+            .checked_sub(1)
+            .and_then(|index| self.line(index));
+        let last = lines
+            .end()
+            .checked_sub(1)
+            .and_then(|index| self.line(index));
+        match (first, last) {
+            (Some(first), Some(last)) if lines.start() <= lines.end() => Ok(match terminator {
+                true => first.start..last.end,
+                false => first.start..last.content_end,
+            }),
+            _ => Err(LinesError::LineOutOfBounds),
+        }
+    }
+
+    /// Zero-indexed line containing an offset, if it is within the input.
+    ///
+    /// Lines are sorted and contiguous, so this is a binary search over their starts. Any input
+    /// has a line starting at 0, so the line is the last one starting at or before the offset.
+    fn line_index(&self, input_offset: usize) -> Option<usize> {
+        match input_offset < self.len {
+            true => Some(self.starts.partition_point(|&start| start <= input_offset) - 1),
+            false => None,
+        }
     }
 
     /// Return the number of lines parsed.
@@ -126,7 +914,580 @@ impl Lines {
     /// Note that if the text ends with the end-of-line delimiter, it does *not* count new line after that.
     /// See tests for an example of this.
     pub fn num_lines(&self) -> usize {
-        self.lines.len()
+        self.starts.len()
+    }
+
+    /// Offset of the first byte of each line, in order, so line `n` starts at index `n - 1`.
+    ///
+    /// An input ending with a terminator has no entry for the empty line after it.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// assert_eq!(Lines::parse("ab\ncd\n").line_starts(), [0, 3]);
+    /// ```
+    pub fn line_starts(&self) -> &[usize] {
+        &self.starts
+    }
+
+    /// Build an index from the offsets at which lines start and the length of the input, such
+    /// as those tracked by another tool.
+    ///
+    /// A start at `total_len` marks the empty line after a final terminator, as some tools record
+    /// it; without one, the last line is taken to be unterminated. The starts alone do not tell
+    /// which terminators end the lines, so each is taken to be a single `\n`. Content spans are
+    /// then one byte too long for lines ending with `\r\n`, which [parse](Lines::parse) tells
+    /// apart.
+    ///
+    /// Fails with [InvalidLineStart](LinesError::InvalidLineStart) unless the starts begin at 0
+    /// and increase up to at most `total_len`.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::from_line_starts(vec![0, 3, 6], 6).unwrap();
+    /// assert_eq!(lines, Lines::parse("ab\ncd\n"));
+    /// assert!(Lines::from_line_starts(vec![0, 3, 3], 6).is_err());
+    /// ```
+    pub fn from_line_starts(mut starts: Vec<usize>, total_len: usize) -> Result<Self, LinesError> {
+        let ends_terminated = starts.last() == Some(&total_len);
+        if ends_terminated {
+            starts.pop();
+        }
+        if starts.is_empty() && total_len > 0 {
+            return Err(LinesError::InvalidLineStart { line: 1 });
+        }
+        let mut previous = None;
+        for (index, &start) in starts.iter().enumerate() {
+            // Only the popped start may be at `total_len`; any other start past the content
+            // would be an empty line that no input produces.
+            let valid = start < total_len
+                && match previous {
+                    None => start == 0,
+                    Some(previous) => previous < start,
+                };
+            if !valid {
+                return Err(LinesError::InvalidLineStart { line: index + 1 });
+            }
+            previous = Some(start);
+        }
+        let terminated = match ends_terminated {
+            true => starts.len(),
+            false => starts.len().saturating_sub(1),
+        };
+        Ok(Lines {
+            endings: vec![LineEnding::Lf; terminated],
+            starts,
+            len: total_len,
+            ..Lines::default()
+        })
+    }
+
+    /// Length in bytes of the parsed input, which is the offset of the end of the input.
+    ///
+    /// Offsets below it are valid for [position](Lines::position), and
+    /// [position_inclusive](Lines::position_inclusive) also accepts it.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("abc\r\nd");
+    /// assert_eq!(lines.total_len(), 6);
+    /// assert!(lines.position(lines.total_len()).is_err());
+    /// ```
+    pub fn total_len(&self) -> usize {
+        self.len
+    }
+
+    /// Check that this index was built from `source`, catching an index that has gone stale.
+    ///
+    /// This compares the length and the position of every line terminator, which is cheap but
+    /// cannot notice edits that keep both. Use [validate_fingerprint](Lines::validate_fingerprint)
+    /// to also compare the contents.
+    ///
+    /// Returns [StaleIndex](LinesError::StaleIndex) on a mismatch.
+    pub fn validate(&self, source: &str) -> Result<(), LinesError> {
+        let bytes = source.as_bytes();
+        let terminators_match = self.lines().all(|line| match line.ending {
+            Some(ending) => {
+                bytes.get(line.content_end..line.end) == Some(ending.as_str().as_bytes())
+            }
+            None => line.content_end == line.end,
+        });
+        match source.len() == self.total_len() && terminators_match {
+            true => Ok(()),
+            false => Err(LinesError::StaleIndex),
+        }
+    }
+
+    /// Check that this index was built from `source`, like [validate](Lines::validate), and
+    /// also that `source` has the given [fingerprint] of the original text.
+    pub fn validate_fingerprint(&self, source: &str, fingerprint: u64) -> Result<(), LinesError> {
+        self.validate(source)?;
+        match crate::fingerprint(source) == fingerprint {
+            true => Ok(()),
+            false => Err(LinesError::StaleIndex),
+        }
+    }
+
+    /// Check the structure of the index, describing the first problem found.
+    ///
+    /// Lines must be non-empty, start at offset 0 and follow each other up to the end of the
+    /// input, and all but the last must be terminated by a [LineEnding] that fits in the line.
+    /// This holds for any index built by this crate, so a failure indicates a bug, such as in
+    /// incremental updates.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// assert_eq!(Lines::parse("a\r\nb").check_invariants(), Ok(()));
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let terminated = self.endings.len();
+        if terminated > self.starts.len() {
+            return Err(format!(
+                "{terminated} terminators for {} lines",
+                self.starts.len()
+            ));
+        }
+        if terminated + 1 < self.starts.len() {
+            return Err(format!("line {} is not terminated", terminated + 1));
+        }
+        let mut expected_start = 0;
+        for (index, &start) in self.starts.iter().enumerate() {
+            let number = index + 1;
+            let end = self.starts.get(index + 1).copied().unwrap_or(self.len);
+            if start != expected_start {
+                return Err(format!(
+                    "line {number} starts at {start} instead of {expected_start}"
+                ));
+            }
+            if start >= end {
+                return Err(format!("line {number} has invalid bounds {start}..{end}"));
+            }
+            let length = self.endings.get(index).map_or(0, |ending| ending.len());
+            if end - start < length {
+                return Err(format!(
+                    "line {number} is shorter than its terminator of {length} bytes"
+                ));
+            }
+            expected_start = end;
+        }
+        match expected_start == self.len {
+            true => Ok(()),
+            false => Err(format!(
+                "lines end at {expected_start} instead of {}",
+                self.len
+            )),
+        }
+    }
+
+    /// Bytes of memory used by the index.
+    #[cfg(feature = "std")]
+    pub(crate) fn memory_usage(&self) -> usize {
+        core::mem::size_of::<Self>()
+            + self.starts.capacity() * core::mem::size_of::<usize>()
+            + self.endings.capacity() * core::mem::size_of::<LineEnding>()
+    }
+
+    /// Offset from which columns are counted on a zero-indexed line, which is after the byte
+    /// order mark on the first line if it is [skipped](ParseOptions::skip_bom).
+    pub(crate) fn column_start(&self, index: usize) -> usize {
+        match index == 0 && self.bom && self.skip_bom {
+            true => BOM.len(),
+            false => self.starts.get(index).copied().unwrap_or(0),
+        }
+    }
+
+    /// Line data for a zero-indexed line.
+    pub(crate) fn line(&self, index: usize) -> Option<Line> {
+        let start = *self.starts.get(index)?;
+        let end = self.starts.get(index + 1).copied().unwrap_or(self.len);
+        let ending = self.endings.get(index).copied();
+        Some(Line {
+            start,
+            content_end: end - ending.map_or(0, LineEnding::len),
+            end,
+            ending,
+        })
+    }
+
+    /// Line data for every line, in order.
+    pub(crate) fn lines(&self) -> impl DoubleEndedIterator<Item = Line> + '_ {
+        (0..self.num_lines()).filter_map(|index| self.line(index))
+    }
+}
+
+/// Incremental scanner for input arriving in chunks, following the rules of [Lines::parse].
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub(crate) struct Scanner {
+    lines: Lines,
+    start: usize,
+    offset: usize,
+    after_cr: bool,
+    /// Number of leading bytes that match the byte order mark.
+    bom_len: usize,
+}
+
+#[cfg(feature = "std")]
+impl Scanner {
+    /// Scan the next chunk of input. A `\r\n` may be split between chunks.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) {
+        if self.bom_len == self.offset {
+            let rest = BOM.get(self.offset..).unwrap_or_default();
+            self.bom_len += chunk.iter().zip(rest).take_while(|(a, b)| a == b).count();
+        }
+        for index in memchr::memchr_iter(b'\n', chunk) {
+            let after_cr = match index.checked_sub(1) {
+                Some(before) => chunk[before] == b'\r',
+                None => self.after_cr,
+            };
+            let ending = match after_cr {
+                true => LineEnding::Crlf,
+                false => LineEnding::Lf,
+            };
+            self.lines.starts.push(self.start);
+            self.lines.endings.push(ending);
+            self.start = self.offset + index + 1;
+        }
+        self.after_cr = chunk.last().map_or(self.after_cr, |&byte| byte == b'\r');
+        self.offset += chunk.len();
+    }
+
+    /// Finish scanning at the end of the input.
+    pub(crate) fn finish(mut self) -> Lines {
+        if self.start < self.offset {
+            self.lines.starts.push(self.start);
+        }
+        self.lines.len = self.offset;
+        self.lines.bom = self.bom_len == BOM.len();
+        self.lines
+    }
+}
+
+/// Direction to move an offset that falls inside something it may not split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bias {
+    /// Move towards the start of the input.
+    Backward,
+    /// Move towards the end of the input.
+    Forward,
+}
+
+/// Unit in which columns are counted, as negotiated with the `positionEncoding` capability of
+/// LSP 3.17.
+///
+/// The default is [Utf16](PositionEncoding::Utf16), which clients must support and assume when
+/// nothing else was negotiated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PositionEncoding {
+    /// Columns count bytes, like [LinePosition::offset].
+    Utf8,
+    /// Columns count UTF-16 code units.
+    #[default]
+    Utf16,
+    /// Columns count characters, that is Unicode scalar values.
+    Utf32,
+}
+
+/// Terminator that ended a line.
+///
+/// Only [Lf](LineEnding::Lf) and [Crlf](LineEnding::Crlf) end lines by default, the others when
+/// [lone_cr](ParseOptions::lone_cr) or [unicode_breaks](ParseOptions::unicode_breaks) are
+/// enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub enum LineEnding {
+    /// `\n`, as on Unix.
+    Lf,
+    /// `\r\n`, as on Windows.
+    Crlf,
+    /// `\r` alone, as on classic Mac OS.
+    Cr,
+    /// U+000C FORM FEED.
+    FormFeed,
+    /// U+000B LINE TABULATION, also known as vertical tab.
+    VerticalTab,
+    /// U+0085 NEXT LINE.
+    NextLine,
+    /// U+2028 LINE SEPARATOR.
+    LineSeparator,
+    /// U+2029 PARAGRAPH SEPARATOR.
+    ParagraphSeparator,
+}
+
+impl LineEnding {
+    /// Text of the terminator.
+    ///
+    /// ```
+    /// use line_position::{LineEnding, Lines};
+    ///
+    /// let lines = Lines::parse("a\r\nb\r\nc\n");
+    /// let ending = lines.line_ending().unwrap();
+    /// assert_eq!(ending, LineEnding::Crlf);
+    /// assert_eq!(ending.as_str(), "\r\n");
+    /// ```
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+            LineEnding::FormFeed => "\u{000C}",
+            LineEnding::VerticalTab => "\u{000B}",
+            LineEnding::NextLine => "\u{0085}",
+            LineEnding::LineSeparator => "\u{2028}",
+            LineEnding::ParagraphSeparator => "\u{2029}",
+        }
+    }
+
+    /// Length of the terminator in bytes.
+    pub(crate) fn len(self) -> usize {
+        self.as_str().len()
+    }
+}
+
+/// Options for parsing input into [Lines], such as resource limits for untrusted input.
+///
+/// ```
+/// use line_position::{LinesError, ParseOptions};
+///
+/// let options = ParseOptions::new().max_len(1024).max_lines(2);
+/// assert_eq!(options.parse("a\nb\n").unwrap().num_lines(), 2);
+/// assert!(matches!(
+///     options.parse("a\nb\nc\n"),
+///     Err(LinesError::TooManyLines { max: 2 })
+/// ));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    max_len: Option<usize>,
+    max_lines: Option<usize>,
+    lone_cr: bool,
+    unicode_breaks: bool,
+    skip_bom: bool,
+}
+
+impl ParseOptions {
+    /// Options without any limits, parsing like [Lines::parse].
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Reject input longer than `max` bytes with [InputTooLong](LinesError::InputTooLong).
+    pub fn max_len(mut self, max: usize) -> Self {
+        self.max_len = Some(max);
+        self
+    }
+
+    /// Reject input with more than `max` lines with [TooManyLines](LinesError::TooManyLines).
+    pub fn max_lines(mut self, max: usize) -> Self {
+        self.max_lines = Some(max);
+        self
+    }
+
+    /// Also treat a `\r` that is not followed by `\n` as a line terminator, as used by classic
+    /// Mac OS, instead of as part of the line.
+    ///
+    /// ```
+    /// use line_position::ParseOptions;
+    ///
+    /// let lines = ParseOptions::new().lone_cr(true).parse("a\rb\r\nc").unwrap();
+    /// assert_eq!(lines.num_lines(), 3);
+    /// assert_eq!(lines.position(2).unwrap().line(), 2);
+    /// ```
+    pub fn lone_cr(mut self, enabled: bool) -> Self {
+        self.lone_cr = enabled;
+        self
+    }
+
+    /// Also treat the other line breaks mandated by Unicode as line terminators: form feed,
+    /// vertical tab, U+0085 NEXT LINE, U+2028 LINE SEPARATOR and U+2029 PARAGRAPH SEPARATOR.
+    ///
+    /// Some language specifications count lines this way, though many, like ECMAScript, only
+    /// recognize U+2028 and U+2029 among these.
+    ///
+    /// ```
+    /// use line_position::ParseOptions;
+    ///
+    /// let lines = ParseOptions::new().unicode_breaks(true).parse("a\u{2028}b\n").unwrap();
+    /// assert_eq!(lines.num_lines(), 2);
+    /// assert_eq!(lines.position(4).unwrap().line(), 2);
+    /// ```
+    pub fn unicode_breaks(mut self, enabled: bool) -> Self {
+        self.unicode_breaks = enabled;
+        self
+    }
+
+    /// Count columns on line 1 from after a UTF-8 byte order mark at the start of the input, as
+    /// editors do, rather than from offset 0.
+    ///
+    /// Offsets inside the byte order mark are at column 0. Line spans still include it.
+    ///
+    /// ```
+    /// use line_position::ParseOptions;
+    ///
+    /// let lines = ParseOptions::new().skip_bom(true).parse("\u{FEFF}abc").unwrap();
+    /// assert_eq!(lines.position(4).unwrap().offset(), 1);
+    /// assert_eq!(lines.offset_of(1, 1).unwrap(), 4);
+    /// ```
+    pub fn skip_bom(mut self, enabled: bool) -> Self {
+        self.skip_bom = enabled;
+        self
+    }
+
+    /// Parse input into [Lines], enforcing the limits.
+    ///
+    /// The length is checked before parsing, and parsing stops at the first line past the limit.
+    pub fn parse(&self, input: &str) -> Result<Lines, LinesError> {
+        if let Some(max) = self.max_len.filter(|&max| input.len() > max) {
+            return Err(LinesError::InputTooLong {
+                len: input.len(),
+                max,
+            });
+        }
+        let input = input.as_bytes();
+        let max_lines = self.max_lines.unwrap_or(usize::MAX);
+        let breaks = Breaks {
+            lone_cr: self.lone_cr,
+            unicode: self.unicode_breaks,
+        };
+        let mut lines = Lines::scan_into(input, breaks, Lines::default(), max_lines);
+        lines.skip_bom = self.skip_bom;
+        match lines.num_lines() > max_lines {
+            true => Err(LinesError::TooManyLines { max: max_lines }),
+            false => Ok(lines),
+        }
+    }
+}
+
+/// Which line terminators to recognize when scanning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub(crate) struct Breaks {
+    /// A `\r` not followed by `\n` terminates a line.
+    lone_cr: bool,
+    /// The other line breaks mandated by Unicode terminate a line.
+    unicode: bool,
+}
+
+/// Interface to a line index, so alternative backends can stand in for [Lines].
+///
+/// [Lines] is the eager, precomputed implementation. Other crates can implement this trait for
+/// lazy, sparse or rope-backed indexes and use them with the helpers in this crate that accept
+/// any `LineIndex`, such as [github_error](format::github_error).
+///
+/// Offsets and columns are in bytes, lines are numbered from 1 and columns from 0.
+pub trait LineIndex {
+    /// Lookup the position of an offset, as in [Lines::position].
+    fn position(&self, offset: usize) -> LinesResult;
+
+    /// Lookup the offset of a column on a line.
+    ///
+    /// The column may point just past the line content, at its terminator. Fails with
+    /// [LineOutOfBounds](LinesError::LineOutOfBounds) or
+    /// [ColumnOutOfBounds](LinesError::ColumnOutOfBounds) otherwise.
+    fn offset_of(&self, line: usize, column: usize) -> Result<usize, LinesError>;
+
+    /// Number of lines, as in [Lines::num_lines].
+    fn num_lines(&self) -> usize;
+
+    /// Byte range of a line, including its terminator.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist.
+    fn line_span(&self, line: usize) -> Result<Range<usize>, LinesError>;
+
+    /// Lookup the position of an offset like [position](LineIndex::position), also accepting the
+    /// length of the input, as in [Lines::position_inclusive].
+    ///
+    /// The default implementation finds the end of the input from the span of the last line, and
+    /// whether that line is terminated from which columns [offset_of](LineIndex::offset_of)
+    /// accepts on it.
+    fn position_inclusive(&self, offset: usize) -> LinesResult {
+        let count = self.num_lines();
+        let len = match count {
+            0 => 0,
+            _ => self.line_span(count)?.end,
+        };
+        if offset != len {
+            return self.position(offset);
+        }
+        if count == 0 {
+            return Ok(LinePosition::new(1, 0));
+        }
+        let last = self.position(offset - 1)?;
+        for column in [last.offset(), last.offset() + 1] {
+            if self.offset_of(count, column).ok() == Some(offset) {
+                return Ok(LinePosition::new(count, column));
+            }
+        }
+        Ok(LinePosition::new(count + 1, 0))
+    }
+}
+
+impl LineIndex for Lines {
+    fn position(&self, offset: usize) -> LinesResult {
+        Lines::position(self, offset)
+    }
+
+    fn offset_of(&self, line: usize, column: usize) -> Result<usize, LinesError> {
+        Lines::offset_of(self, line, column)
+    }
+
+    fn num_lines(&self) -> usize {
+        Lines::num_lines(self)
+    }
+
+    fn line_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+        Lines::line_span(self, line)
+    }
+
+    fn position_inclusive(&self, offset: usize) -> LinesResult {
+        Lines::position_inclusive(self, offset)
+    }
+}
+
+/// Compute a fingerprint of some text, for detecting whether it has changed.
+///
+/// This is a 64-bit FNV-1a hash, so it is cheap and stable across platforms and releases, which
+/// makes it suitable for persisting. It is not resistant to deliberate collisions.
+pub fn fingerprint(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Check that `offset` is within `text`, including its end, and on a character boundary.
+pub(crate) fn check_offset(text: &str, offset: usize) -> Result<(), LinesError> {
+    match (offset > text.len(), text.is_char_boundary(offset)) {
+        (true, _) => Err(LinesError::OffsetOutOfBounds {
+            offset,
+            max: text.len(),
+        }),
+        (false, false) => Err(LinesError::NotCharBoundary { offset }),
+        (false, true) => Ok(()),
+    }
+}
+
+/// Check that `range` is a byte range of `text` that is not reversed and lies on character
+/// boundaries, reporting the offending offset otherwise.
+pub(crate) fn check_range(text: &str, range: &Range<usize>) -> Result<(), LinesError> {
+    check_offset(text, range.end)?;
+    check_offset(text, range.start)?;
+    match range.start <= range.end {
+        true => Ok(()),
+        false => Err(LinesError::OffsetOutOfBounds {
+            offset: range.start,
+            max: range.end,
+        }),
     }
 }
 
@@ -198,15 +1559,638 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn line_of() -> Result<(), LinesError> {
+        let lines = Lines::parse("abc\ndef\n");
+
+        assert_eq!(lines.line_of(0)?, 1, "a on line 1");
+        assert_eq!(lines.line_of(3)?, 1, "newline on line 1");
+        assert_eq!(lines.line_of(4)?, 2, "d on line 2");
+        assert!(lines.line_of(8).is_err(), "out of bounds");
+        assert_eq!(lines.line_of_unchecked(5), 2, "e on line 2");
+        assert_eq!(lines.line_of_unchecked(100), 2, "past the end");
+        assert_eq!(Lines::parse("").line_of_unchecked(0), 1, "empty input");
+
+        Ok(())
+    }
+
+    #[test]
+    fn column_of() -> Result<(), LinesError> {
+        let lines = Lines::parse("abc\r\ndef\r\n");
+
+        assert_eq!(lines.column_of(1, 2)?, 2, "c at offset 2");
+        assert_eq!(lines.column_of(2, 6)?, 1, "e at offset 1");
+        assert!(matches!(
+            lines.column_of(1, 6),
+            Err(LinesError::OffsetOutOfBounds { offset: 6, max: 5 })
+        ));
+        assert!(matches!(
+            lines.column_of(0, 0),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.column_of(3, 10),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn offset_of_round_trips() -> Result<(), LinesError> {
+        let input = "abc\r\n\r\ndé";
+        let lines = Lines::parse(input);
+
+        for offset in [0, 2, 3, 5, 7, 8] {
+            let position = lines.position(offset)?;
+            assert_eq!(lines.offset_of(position.line(), position.offset())?, offset);
+        }
+        assert_eq!(lines.offset_of(3, 3)?, input.len(), "end of input");
+        assert!(matches!(
+            lines.offset_of(2, 1),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.offset_of(0, 0),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn line_spans() -> Result<(), LinesError> {
+        let lines = Lines::parse("ab\n\ncd");
+
+        assert_eq!(lines.line_span(1)?, 0..3);
+        assert_eq!(lines.line_content_span(2)?, 3..3);
+        assert_eq!(lines.line_span(3)?, lines.line_content_span(3)?);
+        assert!(matches!(
+            lines.line_span(0),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.line_content_span(4),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_reader_matches_parse() -> io::Result<()> {
+        for input in [
+            "",
+            "a",
+            "a\n\nb\n",
+            "a\nb\r\nc\n\r\n",
+            "\r\n",
+            "a\r",
+            "\n\r\n",
+            "\u{FEFF}a\r\nb",
+        ] {
+            // A one-byte buffer splits every `\r\n` across reads.
+            for capacity in [1, 2, 64] {
+                let reader = io::BufReader::with_capacity(capacity, input.as_bytes());
+                assert_eq!(
+                    Lines::from_reader(reader)?,
+                    Lines::parse(input),
+                    "{input:?}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Backend where every line is exactly `width` bytes, including its terminator.
+    struct FixedWidth {
+        width: usize,
+        len: usize,
+    }
+
+    impl LineIndex for FixedWidth {
+        fn position(&self, offset: usize) -> LinesResult {
+            match offset < self.len {
+                true => Ok(LinePosition::new(
+                    offset / self.width + 1,
+                    offset % self.width,
+                )),
+                false => Err(LinesError::OffsetOutOfBounds {
+                    offset,
+                    max: self.len,
+                }),
+            }
+        }
+
+        fn offset_of(&self, line: usize, column: usize) -> Result<usize, LinesError> {
+            Ok(self.line_span(line)?.start + column)
+        }
+
+        fn num_lines(&self) -> usize {
+            self.len.div_ceil(self.width)
+        }
+
+        fn line_span(&self, line: usize) -> Result<Range<usize>, LinesError> {
+            match line >= 1 && line <= self.num_lines() {
+                true => Ok((line - 1) * self.width..(line * self.width).min(self.len)),
+                false => Err(LinesError::LineOutOfBounds),
+            }
+        }
+    }
+
+    #[test]
+    fn text_of_lines() -> Result<(), LinesError> {
+        let source = "one\r\ntwo\r\nthree";
+        let lines = Lines::parse(source);
+
+        assert_eq!(lines.text_of_lines(source, 1..=1)?, "one\r\n");
+        assert_eq!(lines.content_of_lines(source, 1..=2)?, "one\r\ntwo");
+        assert_eq!(lines.text_of_lines(source, 2..=3)?, "two\r\nthree");
+        assert_eq!(lines.content_of_lines(source, 3..=3)?, "three");
+        assert!(matches!(
+            lines.text_of_lines(source, 0..=1),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.text_of_lines(source, 2..=4),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.text_of_lines(source, RangeInclusive::new(3, 2)),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            lines.text_of_lines("one", 1..=2),
+            Err(LinesError::StaleIndex)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn line_index_trait() -> Result<(), LinesError> {
+        let input = "abc\ndef\nghi\n";
+        let backends: [&dyn LineIndex; 2] = [
+            &Lines::parse(input),
+            &FixedWidth {
+                width: 4,
+                len: input.len(),
+            },
+        ];
+
+        for index in backends {
+            assert_eq!(index.num_lines(), 3, "number of lines is 3");
+            assert_eq!(index.position(5)?.line(), 2, "e on line 2");
+            assert_eq!(index.position(5)?.offset(), 1, "e at offset 1");
+            assert_eq!(index.offset_of(3, 2)?, 10, "i at offset 10");
+            assert_eq!(index.line_span(2)?, 4..8, "line 2 span");
+            assert!(index.line_span(4).is_err(), "no line 4");
+            assert_eq!(
+                format::github_error(index, "a.txt", 4..7, "bad")?,
+                "::error file=a.txt,line=2,col=1,endLine=2,endColumn=3::bad"
+            );
+        }
+        let lines = Lines::parse(input);
+        assert!(matches!(
+            LineIndex::offset_of(&lines, 1, 4),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn position_tuples() -> Result<(), LinesError> {
+        let lines = Lines::parse("abc\ndef\n");
+        let (line, offset) = lines.position(5)?.into();
+        assert_eq!((line, offset), (2, 1), "e at 2:1");
+
+        let position = LinePosition::try_from((2, 1))?;
+        assert_eq!(position.line(), 2);
+        assert_eq!(position.offset(), 1);
+        assert!(LinePosition::try_from((0, 1)).is_err(), "no line 0");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parse_file() -> io::Result<()> {
+        let path = std::env::temp_dir().join("line-position-parse-file.txt");
+        fs::write(&path, "abc\ndef\n")?;
+        let (contents, lines) = Lines::parse_file(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(contents, "abc\ndef\n");
+        assert_eq!(lines.num_lines(), 2, "number of lines is 2");
+        assert!(
+            Lines::parse_file(&path).is_err(),
+            "missing file is an error"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        assert_eq!(fingerprint(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fingerprint("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(fingerprint("abc\n"), fingerprint("abc\r\n"));
+    }
+
+    #[test]
+    fn try_parse() -> Result<(), LinesError> {
+        for input in ["", "a", "a\nb\n", "a\r\nb\nc"] {
+            assert_eq!(Lines::try_parse(input)?, Lines::parse(input));
+        }
+
+        let mut lines: Vec<usize> = Vec::new();
+        let error = LinesError::from(lines.try_reserve(usize::MAX).unwrap_err());
+        assert_eq!(error.to_string(), "failed to allocate memory for the index");
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_options() -> Result<(), LinesError> {
+        assert_eq!(ParseOptions::new().parse("a\nb")?.num_lines(), 2);
+
+        let options = ParseOptions::new().max_len(4).max_lines(2);
+        assert_eq!(options.parse("a\nb\n")?.num_lines(), 2);
+        assert_eq!(options.parse("a\nbc")?.num_lines(), 2);
+        assert!(matches!(
+            options.parse("a\nbcd"),
+            Err(LinesError::InputTooLong { len: 5, max: 4 })
+        ));
+        assert!(matches!(
+            options.parse("\n\n\n"),
+            Err(LinesError::TooManyLines { max: 2 })
+        ));
+        assert!(matches!(
+            ParseOptions::new().max_lines(0).parse("a"),
+            Err(LinesError::TooManyLines { max: 0 })
+        ));
+        assert_eq!(ParseOptions::new().max_lines(0).parse("")?.num_lines(), 0);
+
+        let lone_cr = ParseOptions::new().lone_cr(true);
+        for (input, spans) in [
+            ("a\rb\r", vec![0..2, 2..4]),
+            ("\r\r\n\n\r", vec![0..1, 1..3, 3..4, 4..5]),
+            ("a\n\rb", vec![0..2, 2..3, 3..4]),
+        ] {
+            let lines = lone_cr.parse(input)?;
+            assert_eq!(lines.check_invariants(), Ok(()), "{input:?}");
+            assert_eq!(
+                lines.into_iter().map(|(_, span)| span).collect::<Vec<_>>(),
+                spans,
+                "{input:?}"
+            );
+        }
+        assert_eq!(ParseOptions::new().parse("a\rb\r")?.num_lines(), 1);
+
+        let input = "a\u{c}b\u{b}c\u{85}d\u{2028}e\u{2029}f\u{2027}";
+        let lines = ParseOptions::new().unicode_breaks(true).parse(input)?;
+        assert_eq!(lines.check_invariants(), Ok(()));
+        assert_eq!(
+            lines.into_iter().map(|(_, span)| span).collect::<Vec<_>>(),
+            vec![0..2, 2..4, 4..7, 7..11, 11..15, 15..19]
+        );
+        assert_eq!(ParseOptions::new().parse(input)?.num_lines(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn line_endings() -> Result<(), LinesError> {
+        let lines = Lines::parse("a\nb\r\nc\r\nd\n");
+        assert_eq!(lines.line_ending_of(2)?, Some(LineEnding::Crlf));
+        assert_eq!(
+            lines.line_ending(),
+            Some(LineEnding::Lf),
+            "ties go to the first"
+        );
+        assert_eq!(
+            Lines::parse("a\nb\r\nc\r\n").line_ending(),
+            Some(LineEnding::Crlf)
+        );
+        assert_eq!(Lines::parse("abc").line_ending(), None);
+        assert!(!Lines::parse("").has_trailing_newline());
+        assert!(Lines::parse("\r\n").has_trailing_newline());
+        assert!(matches!(
+            lines.line_ending_of(0),
+            Err(LinesError::LineOutOfBounds)
+        ));
+
+        let options = ParseOptions::new().lone_cr(true).unicode_breaks(true);
+        let lines = options.parse("a\rb\u{2029}c\u{0085}d\u{000C}")?;
+        let endings: Result<Vec<_>, _> = (1..=4).map(|line| lines.line_ending_of(line)).collect();
+        assert_eq!(
+            endings?,
+            [
+                LineEnding::Cr,
+                LineEnding::ParagraphSeparator,
+                LineEnding::NextLine,
+                LineEnding::FormFeed
+            ]
+            .map(Some)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn position_inclusive() -> Result<(), LinesError> {
+        let lines = Lines::parse("ab\r\ncd");
+        assert_eq!(lines.position_inclusive(1)?.offset(), 1, "same as position");
+        assert_eq!(<(usize, usize)>::from(lines.position_inclusive(6)?), (2, 2));
+        assert!(matches!(
+            lines.position_inclusive(7),
+            Err(LinesError::OffsetOutOfBounds { offset: 7, max: 6 })
+        ));
+
+        let lines = Lines::parse("ab\r\n");
+        assert_eq!(<(usize, usize)>::from(lines.position_inclusive(4)?), (2, 0));
+        assert_eq!(
+            <(usize, usize)>::from(Lines::parse("").position_inclusive(0)?),
+            (1, 0)
+        );
+        assert!(Lines::parse("").position_inclusive(1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn position_clamped() {
+        let lines = Lines::parse("ab\ncd\n");
+        assert_eq!(<(usize, usize)>::from(lines.position_clamped(4)), (2, 1));
+        assert_eq!(<(usize, usize)>::from(lines.position_clamped(6)), (3, 0));
+        assert_eq!(
+            <(usize, usize)>::from(lines.position_clamped(usize::MAX)),
+            (3, 0)
+        );
+        assert_eq!(
+            <(usize, usize)>::from(Lines::parse("").position_clamped(5)),
+            (1, 0)
+        );
+    }
+
+    #[test]
+    fn positions_are_values() {
+        let lines = Lines::parse("ab\ncd\n");
+        let mut positions: Vec<_> = [4, 0, 1, 3, 1]
+            .map(|offset| lines.position(offset).unwrap())
+            .into();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(
+            positions,
+            [(1, 0), (1, 1), (2, 0), (2, 1)].map(|(line, offset)| LinePosition::new(line, offset))
+        );
+
+        let position = positions[0];
+        let set: std::collections::HashSet<_> = [position, positions[0]].into();
+        assert_eq!(set.len(), 1);
+        assert!(LinePosition::new(1, 9) < LinePosition::new(2, 0));
+    }
+
+    #[test]
+    fn position_checked() -> Result<(), LinesError> {
+        let source = "a😀\nb";
+        let lines = Lines::parse(source);
+        assert_eq!(lines.position_checked(source, 5)?.offset(), 5);
+        assert_eq!(lines.position_checked(source, 6)?.line(), 2);
+        for offset in 2..5 {
+            assert!(matches!(
+                lines.position_checked(source, offset),
+                Err(LinesError::NotCharBoundary { offset: o }) if o == offset
+            ));
+        }
+        assert!(matches!(
+            lines.position_checked(source, 7),
+            Err(LinesError::OffsetOutOfBounds { offset: 7, max: 7 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn range() -> Result<(), LinesError> {
+        let lines = Lines::parse("ab\ncd\n");
+        let (start, end) = lines.range(Span::new(1, 4))?;
+        assert_eq!(
+            (start, end),
+            (LinePosition::new(1, 1), LinePosition::new(2, 1))
+        );
+        let (start, end) = lines.range(6..6)?;
+        assert_eq!(
+            (start, end),
+            (LinePosition::new(3, 0), LinePosition::new(3, 0))
+        );
+        assert!(matches!(
+            lines.range(2..7),
+            Err(LinesError::OffsetOutOfBounds { offset: 7, max: 6 })
+        ));
+        let (start, end) = (4, 2);
+        assert!(matches!(
+            lines.range(start..end),
+            Err(LinesError::OffsetOutOfBounds { offset: 4, max: 2 })
+        ));
+
+        let span = Span::from(start..end);
+        assert!(span.is_empty() && !span.contains(3));
+        assert!(!Span::new(2, 2).overlaps(Span::new(0, 4)));
+        assert_eq!(Range::from(Span::new(1, 3)), 1..3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_line_starts() -> Result<(), LinesError> {
+        for input in ["", "a", "\n", "ab\ncd", "ab\n\ncd\n"] {
+            let lines = Lines::parse(input);
+            let mut starts = lines.line_starts().to_vec();
+            if lines.has_trailing_newline() {
+                starts.push(input.len());
+            }
+            let rebuilt = Lines::from_line_starts(starts, input.len())?;
+            assert_eq!(rebuilt, lines, "{input:?}");
+        }
+        let lines = Lines::from_line_starts(vec![0, 4, 7], 7)?;
+        assert_eq!(lines.check_invariants(), Ok(()));
+        assert_eq!(lines.line_content_span(1)?, 0..3, "assumed to end in \\n");
+
+        for (starts, len, line) in [
+            (vec![], 3, 1),
+            (vec![1, 2], 3, 1),
+            (vec![0, 2, 2], 3, 3),
+            (vec![0, 2, 1], 3, 3),
+            (vec![0, 4], 3, 2),
+            (vec![0, 0], 0, 1),
+            (vec![0, 3, 3], 3, 2),
+        ] {
+            assert!(matches!(
+                Lines::from_line_starts(starts, len),
+                Err(LinesError::InvalidLineStart { line: l }) if l == line
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn byte_order_mark() -> Result<(), LinesError> {
+        let input = "\u{FEFF}ab\ncd";
+        let lines = Lines::parse(input);
+        assert!(lines.has_bom());
+        assert_eq!(lines.position(4)?.offset(), 4, "counted from offset 0");
+        assert!(!Lines::parse("ab\u{FEFF}").has_bom());
+
+        let lines = ParseOptions::new().skip_bom(true).parse(input)?;
+        assert_eq!(lines.position(1)?.offset(), 0, "inside the mark");
+        assert_eq!(lines.position(4)?.offset(), 1);
+        assert_eq!(lines.position(7)?.offset(), 1, "line 2 is unaffected");
+        assert_eq!(lines.column_of(1, 5)?, 2);
+        assert_eq!(lines.offset_of(1, 2)?, 5);
+        assert!(matches!(
+            lines.offset_of(1, 3),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert_eq!(lines.line_span(1)?, 0..6, "spans include the mark");
+        assert_eq!(lines.line_len(1)?, 2);
+
+        #[cfg(feature = "std")]
+        assert!(Lines::from_reader(input.as_bytes()).is_ok_and(|lines| lines.has_bom()));
+        assert!(!ParseOptions::new().skip_bom(true).parse("ab")?.has_bom());
+
+        Ok(())
+    }
+
+    #[test]
+    fn error_converts_to_dyn_error() {
+        fn lookup() -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(Lines::parse("abc").position(5)?.line())
+        }
+
+        let error = lookup().unwrap_err();
+        assert_eq!(error.to_string(), "offset 5 outside of input bounds of 3");
+        assert!(error.downcast_ref::<LinesError>().is_some());
+    }
+
+    #[test]
+    fn index_snapshots() {
+        assert_eq!(Lines::default(), Lines::parse(""));
+
+        let lines = Lines::parse("a\nb\n");
+        let snapshot = lines.clone();
+        assert_eq!(snapshot, lines);
+        assert_ne!(snapshot, Lines::parse("a\nb\nc\n"));
+        assert_ne!(
+            Lines::parse("\u{FEFF}a"),
+            ParseOptions::new()
+                .skip_bom(true)
+                .parse("\u{FEFF}a")
+                .unwrap()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() -> Result<(), LinesError> {
+        let lines = ParseOptions::new()
+            .lone_cr(true)
+            .parse("\u{FEFF}a\rb\r\nc")?;
+        let json = serde_json::to_string(&lines).unwrap();
+        assert_eq!(serde_json::from_str::<Lines>(&json).unwrap(), lines);
+
+        let position = lines.position(5)?;
+        let json = serde_json::to_string(&position).unwrap();
+        assert_eq!(json, r#"{"line":2,"offset":0}"#);
+        assert_eq!(
+            serde_json::from_str::<LinePosition>(&json).unwrap(),
+            position
+        );
+
+        let error = lines.position(10).unwrap_err();
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(json, r#"{"OffsetOutOfBounds":{"offset":10,"max":9}}"#);
+        assert!(matches!(
+            serde_json::from_str(&json).unwrap(),
+            LinesError::OffsetOutOfBounds { offset: 10, max: 9 }
+        ));
+        let allocation = LinesError::from(Vec::<u8>::new().try_reserve(usize::MAX).unwrap_err());
+        assert!(serde_json::to_string(&allocation).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_invariants() {
+        for input in ["", "a", "a\n", "a\nb", "a\r\n\r\nb\n", "\n\n"] {
+            assert_eq!(Lines::parse(input).check_invariants(), Ok(()), "{input:?}");
+        }
+
+        let index = |starts: Vec<usize>, endings: Vec<LineEnding>, len| Lines {
+            starts,
+            endings,
+            len,
+            ..Lines::default()
+        };
+        let cases = [
+            (index(vec![1], vec![], 2), "line 1 starts at 1 instead of 0"),
+            (
+                index(vec![0, 0], vec![LineEnding::Lf], 1),
+                "line 1 has invalid bounds 0..0",
+            ),
+            (index(vec![0], vec![], 0), "line 1 has invalid bounds 0..0"),
+            (index(vec![0, 1], vec![], 2), "line 1 is not terminated"),
+            (
+                index(vec![0, 1], vec![LineEnding::Crlf], 2),
+                "line 1 is shorter than its terminator of 2 bytes",
+            ),
+            (
+                index(vec![0], vec![LineEnding::Lf; 2], 2),
+                "2 terminators for 1 lines",
+            ),
+            (index(vec![], vec![], 3), "lines end at 0 instead of 3"),
+        ];
+        for (lines, message) in cases {
+            assert_eq!(lines.check_invariants(), Err(message.to_string()));
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let original = "abc\ndef\n";
+        let lines = Lines::parse(original);
+
+        assert!(lines.validate(original).is_ok());
+        assert!(lines.validate("abc\ndef").is_err(), "length differs");
+        assert!(lines.validate("abcd\nef\n").is_err(), "terminator moved");
+        assert!(lines.validate("abc\nxyz\n").is_ok(), "same shape");
+        assert!(matches!(
+            lines.validate_fingerprint("abc\nxyz\n", fingerprint(original)),
+            Err(LinesError::StaleIndex)
+        ));
+        assert!(lines
+            .validate_fingerprint(original, fingerprint(original))
+            .is_ok());
+    }
+
     #[test]
     fn mixed_eol() -> Result<(), LinesError> {
         let input = "abcdefg\r\nhijklmnop\nqrstuv";
         let lines = Lines::parse(input);
 
-        assert_eq!(lines.num_lines(), 2, "number of lines is 2");
+        assert_eq!(lines.num_lines(), 3, "number of lines is 3");
         assert_eq!(lines.position(8)?.line(), 1, "first newline on line 1");
         assert_eq!(lines.position(9)?.line(), 2, "h on line 2");
-        assert_eq!(lines.position(24)?.line(), 2, "v on line 2");
+        assert_eq!(lines.position(18)?.line(), 2, "second newline on line 2");
+        assert_eq!(lines.position(19)?.line(), 3, "q on line 3");
+        assert_eq!(lines.position(24)?.offset(), 5, "v at offset 5");
+        assert_eq!(lines.line_content_span(1)?, 0..7, "line 1 without \\r\\n");
+        assert_eq!(lines.line_content_span(2)?, 9..18, "line 2 without \\n");
         assert!(lines.position(25).is_err(), "out of bounds");
 
         Ok(())
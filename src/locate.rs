@@ -0,0 +1,142 @@
+//! Recovery of byte offsets from the line and column locations parsers report.
+//!
+//! Errors from parsers such as `serde_json` only carry a line and column. These helpers turn them
+//! back into a byte range of the source, for rendering with [snippet](crate::format::snippet) or
+//! converting with the other formatters. Parsers that already report byte spans, such as `toml`
+//! with `toml::de::Error::span`, can be passed to those directly.
+
+use core::ops::Range;
+
+use crate::{LineIndex, LinesError};
+
+/// Byte range of the character at a one-indexed line and one-indexed byte column of `source`, the
+/// text `lines` was built from.
+///
+/// The range is empty when the column points at a line terminator or the end of the input. The
+/// line just after a final terminator is accepted with column 1, since parsers report errors
+/// at the end of such input there.
+///
+/// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) or
+/// [ColumnOutOfBounds](LinesError::ColumnOutOfBounds), including for column 0.
+///
+/// ```
+/// use line_position::{locate::span_at, Lines};
+///
+/// let source = "[1,\n é]\n";
+/// let lines = Lines::parse(source);
+/// assert_eq!(span_at(&lines, source, 2, 2).unwrap(), 5..7);
+/// assert_eq!(span_at(&lines, source, 3, 1).unwrap(), 9..9);
+/// ```
+pub fn span_at(
+    lines: &(impl LineIndex + ?Sized),
+    source: &str,
+    line: usize,
+    column: usize,
+) -> Result<Range<usize>, LinesError> {
+    let column = column.checked_sub(1).ok_or(LinesError::ColumnOutOfBounds)?;
+    let offset = match line == lines.num_lines() + 1 {
+        true if ends_line(source) => match column {
+            0 => source.len(),
+            _ => return Err(LinesError::ColumnOutOfBounds),
+        },
+        _ => lines.offset_of(line, column)?,
+    };
+    let len = source
+        .get(offset..)
+        .and_then(|rest| rest.chars().next())
+        .filter(|&c| c != '\r' && c != '\n')
+        .map_or(0, char::len_utf8);
+    Ok(offset..offset + len)
+}
+
+/// Byte range of the location reported by a `serde_json` error in `source`, the text `lines` was
+/// built from, as in [span_at].
+///
+/// Returns [None] for errors without a location, such as I/O errors, and for locations outside
+/// the source.
+///
+/// Requires the `serde_json` feature.
+///
+/// ```
+/// use line_position::{locate::serde_json_error_span, Lines};
+///
+/// let source = "{\n  \"a\": tru\n}";
+/// let error = serde_json::from_str::<serde_json::Value>(source).unwrap_err();
+/// let lines = Lines::parse(source);
+/// assert_eq!(serde_json_error_span(&lines, source, &error), Some(12..12));
+/// ```
+#[cfg(feature = "serde_json")]
+pub fn serde_json_error_span(
+    lines: &(impl LineIndex + ?Sized),
+    source: &str,
+    error: &serde_json::Error,
+) -> Option<Range<usize>> {
+    match (error.line(), error.column()) {
+        (0, _) => None,
+        // Column 0 means the terminator of the previous line was the last byte read.
+        (line, 0) if line > 1 => {
+            let span = lines.line_span(line - 1).ok()?;
+            let text = source.get(span.clone())?;
+            let content = text.trim_end_matches('\n').trim_end_matches('\r');
+            let end = span.start + content.len();
+            Some(end..end)
+        }
+        (line, column) => span_at(lines, source, line, column.max(1)).ok(),
+    }
+}
+
+/// Whether the input is empty or ends with a line terminator.
+fn ends_line(source: &str) -> bool {
+    source.is_empty() || source.ends_with('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lines;
+
+    #[test]
+    fn span_at() -> Result<(), LinesError> {
+        let source = "ab\r\ncd";
+        let lines = Lines::parse(source);
+
+        assert_eq!(super::span_at(&lines, source, 1, 1)?, 0..1);
+        assert_eq!(super::span_at(&lines, source, 1, 3)?, 2..2);
+        assert_eq!(super::span_at(&lines, source, 2, 3)?, 6..6);
+        assert!(matches!(
+            super::span_at(&lines, source, 1, 0),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert!(matches!(
+            super::span_at(&lines, source, 1, 4),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert!(matches!(
+            super::span_at(&lines, source, 3, 1),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert_eq!(super::span_at(&Lines::parse(""), "", 1, 1)?, 0..0);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn serde_json_error_span() {
+        let cases = [
+            ("[1, 2", 4..5),
+            ("[1, 2\n", 5..5),
+            ("[1,\r\n x]", 6..7),
+            ("{\"é\": }", 7..8),
+        ];
+        for (source, span) in cases {
+            let error = serde_json::from_str::<serde_json::Value>(source).unwrap_err();
+            let lines = Lines::parse(source);
+            assert_eq!(
+                super::serde_json_error_span(&lines, source, &error),
+                Some(span),
+                "{source:?}: {error}"
+            );
+        }
+    }
+}
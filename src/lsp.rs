@@ -0,0 +1,976 @@
+//! Interop with [lsp-types](https://crates.io/crates/lsp-types).
+//!
+//! Requires the `lsp-types` feature.
+//!
+//! The [RangeConverter] and the helpers built on it count the character field of positions in
+//! the [PositionEncoding] negotiated with the client, UTF-16 unless the client offers another
+//! one. The plain conversions between [LinePosition] and [Position] copy byte offsets and so are
+//! only correct for the `utf-8` encoding.
+
+use std::ops::Range;
+
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, FoldingRange,
+    Location, NumberOrString, Position, PositionEncodingKind, SelectionRange,
+    TextDocumentContentChangeEvent, Uri,
+};
+
+use crate::document::TextDocument;
+use crate::{LinePosition, Lines, LinesError, PositionEncoding, PositionRange};
+
+/// Convert a position into an LSP position, shifting the line to start with 0.
+///
+/// The offset becomes the character unchanged, so it is only correct for the `utf-8` position
+/// encoding. Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) for line 0 or lines that
+/// do not fit into a `u32`, or with [ColumnOutOfBounds](LinesError::ColumnOutOfBounds) for such
+/// offsets.
+///
+/// ```
+/// use line_position::LinePosition;
+/// use lsp_types::Position;
+///
+/// let position = Position::try_from(LinePosition::new(2, 5)).unwrap();
+/// assert_eq!(position, Position::new(1, 5));
+/// assert_eq!(LinePosition::from(position).line(), 2);
+/// ```
+impl TryFrom<LinePosition> for Position {
+    type Error = LinesError;
+
+    fn try_from(position: LinePosition) -> Result<Self, Self::Error> {
+        let line = position
+            .line()
+            .checked_sub(1)
+            .and_then(|line| u32::try_from(line).ok())
+            .ok_or(LinesError::LineOutOfBounds)?;
+        let character =
+            u32::try_from(position.offset()).map_err(|_| LinesError::ColumnOutOfBounds)?;
+        Ok(Position::new(line, character))
+    }
+}
+
+/// Convert an LSP position into a position, shifting the line to start with 1.
+impl From<Position> for LinePosition {
+    fn from(position: Position) -> Self {
+        LinePosition::new(position.line as usize + 1, position.character as usize)
+    }
+}
+
+/// Convert a range into an LSP range, failing as the conversion of its positions does.
+impl TryFrom<PositionRange> for lsp_types::Range {
+    type Error = LinesError;
+
+    fn try_from(range: PositionRange) -> Result<Self, Self::Error> {
+        let PositionRange { start, end } = range;
+        Ok(lsp_types::Range::new(start.try_into()?, end.try_into()?))
+    }
+}
+
+/// Convert an LSP range into a range.
+impl From<lsp_types::Range> for PositionRange {
+    fn from(range: lsp_types::Range) -> Self {
+        PositionRange::new(range.start.into(), range.end.into())
+    }
+}
+
+impl From<PositionEncoding> for PositionEncodingKind {
+    fn from(encoding: PositionEncoding) -> Self {
+        match encoding {
+            PositionEncoding::Utf8 => PositionEncodingKind::UTF8,
+            PositionEncoding::Utf16 => PositionEncodingKind::UTF16,
+            PositionEncoding::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+}
+
+/// Pick the first of the encodings a client offers in its `general.positionEncodings`
+/// capability that this crate supports, or UTF-16 if it offers none, as LSP 3.17 prescribes.
+///
+/// ```
+/// use line_position::{lsp::negotiate_encoding, PositionEncoding};
+/// use lsp_types::PositionEncodingKind;
+///
+/// let offered = [PositionEncodingKind::new("utf-7"), PositionEncodingKind::UTF8];
+/// assert_eq!(negotiate_encoding(Some(&offered)), PositionEncoding::Utf8);
+/// assert_eq!(negotiate_encoding(None), PositionEncoding::Utf16);
+/// ```
+pub fn negotiate_encoding(offered: Option<&[PositionEncodingKind]>) -> PositionEncoding {
+    offered
+        .unwrap_or_default()
+        .iter()
+        .find_map(|kind| match kind.as_str() {
+            "utf-8" => Some(PositionEncoding::Utf8),
+            "utf-16" => Some(PositionEncoding::Utf16),
+            "utf-32" => Some(PositionEncoding::Utf32),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+impl TextDocument {
+    /// Apply the content changes of an LSP `didChange` notification in order, then set the
+    /// version of the document.
+    ///
+    /// Changes without a range replace the whole text. Ranges are interpreted in the given
+    /// position encoding, as a [RangeConverter] with [ClampPolicy::Strict] does. Fails with
+    /// [LineOutOfBounds](LinesError::LineOutOfBounds) or
+    /// [ColumnOutOfBounds](LinesError::ColumnOutOfBounds) if a range is outside the text, or
+    /// [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if it is reversed. The changes before a
+    /// failing one have then been applied, but the version is unchanged.
+    ///
+    /// ```
+    /// use line_position::{document::TextDocument, PositionEncoding};
+    /// use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+    ///
+    /// let mut document = TextDocument::new("let 😀 = 1;\n").with_version(1);
+    /// let change = TextDocumentContentChangeEvent {
+    ///     range: Some(Range::new(Position::new(0, 9), Position::new(0, 10))),
+    ///     range_length: None,
+    ///     text: "2".to_string(),
+    /// };
+    /// document.apply_changes(2, &[change], PositionEncoding::Utf16).unwrap();
+    /// assert_eq!((document.text(), document.version()), ("let 😀 = 2;\n", 2));
+    /// ```
+    pub fn apply_changes(
+        &mut self,
+        version: i32,
+        changes: &[TextDocumentContentChangeEvent],
+        encoding: PositionEncoding,
+    ) -> Result<(), LinesError> {
+        for change in changes {
+            match change.range {
+                Some(range) => {
+                    let converter = RangeConverter::new(
+                        self.lines(),
+                        self.text(),
+                        encoding,
+                        ClampPolicy::Strict,
+                    );
+                    let start = converter.to_offset(range.start)?;
+                    let end = converter.to_offset(range.end)?;
+                    self.apply_edit(start..end, &change.text)?;
+                }
+                None => self.set_text(change.text.as_str()),
+            }
+        }
+        self.set_version(version);
+        Ok(())
+    }
+}
+
+/// How conversions treat endpoints that fall outside the input or past the end of a line.
+///
+/// The end of the input is always a valid endpoint, since LSP ranges have exclusive ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClampPolicy {
+    /// Reject offsets past the end of the input, lines past the last line, and characters past
+    /// the end of their line's content.
+    #[default]
+    Strict,
+    /// Move such endpoints back to the nearest valid position, as the LSP specification asks of
+    /// characters past the end of a line.
+    Clamp,
+}
+
+/// Converter between byte ranges and LSP [Range](lsp_types::Range)s, in both directions.
+///
+/// The character field of LSP positions counts units of the negotiated [PositionEncoding] within
+/// the line content, so the converter needs the text as well as its index. Columns on line 1 are
+/// counted as in [Lines::position], after a byte order mark skipped with
+/// [skip_bom](crate::ParseOptions::skip_bom).
+///
+/// ```
+/// use line_position::{lsp::{ClampPolicy, RangeConverter}, Lines, PositionEncoding};
+/// use lsp_types::Position;
+///
+/// let source = "aé\ndef\n";
+/// let lines = Lines::parse(source);
+/// let converter =
+///     RangeConverter::new(&lines, source, PositionEncoding::Utf16, ClampPolicy::Clamp);
+/// let range = converter.to_lsp(3..8).unwrap();
+/// assert_eq!(range.start, Position::new(0, 2));
+/// assert_eq!(range.end, Position::new(2, 0));
+/// assert_eq!(converter.from_lsp(range).unwrap(), 3..8);
+///
+/// let past_end = lsp_types::Range::new(Position::new(0, 1), Position::new(0, 99));
+/// assert_eq!(converter.from_lsp(past_end).unwrap(), 1..3);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RangeConverter<'a> {
+    lines: &'a Lines,
+    source: &'a str,
+    encoding: PositionEncoding,
+    policy: ClampPolicy,
+}
+
+impl<'a> RangeConverter<'a> {
+    /// Create a converter for `source`, the text indexed by `lines`, counting characters in the
+    /// given encoding and using the given policy.
+    ///
+    /// Conversions fail with [StaleIndex](LinesError::StaleIndex) if `source` does not match the
+    /// index.
+    pub fn new(
+        lines: &'a Lines,
+        source: &'a str,
+        encoding: PositionEncoding,
+        policy: ClampPolicy,
+    ) -> Self {
+        RangeConverter {
+            lines,
+            source,
+            encoding,
+            policy,
+        }
+    }
+
+    /// Convert a byte range into an LSP range.
+    pub fn to_lsp(&self, range: Range<usize>) -> Result<lsp_types::Range, LinesError> {
+        Ok(lsp_types::Range::new(
+            self.to_position(range.start)?,
+            self.to_position(range.end)?,
+        ))
+    }
+
+    /// Convert an LSP range into a byte range.
+    pub fn from_lsp(&self, range: lsp_types::Range) -> Result<Range<usize>, LinesError> {
+        Ok(self.to_offset(range.start)?..self.to_offset(range.end)?)
+    }
+
+    /// Convert a byte offset into an LSP position.
+    pub fn to_position(&self, offset: usize) -> Result<Position, LinesError> {
+        let len = self.lines.total_len();
+        let offset = match (offset > len, self.policy) {
+            (true, ClampPolicy::Strict) => {
+                return Err(LinesError::OffsetOutOfBounds { offset, max: len })
+            }
+            (true, ClampPolicy::Clamp) => len,
+            (false, _) => offset,
+        };
+        if offset < len {
+            let position = self
+                .lines
+                .position_with_encoding(self.source, offset, self.encoding)?;
+            return Ok(Position::new(
+                to_u32(position.line() - 1),
+                to_u32(position.offset()),
+            ));
+        }
+
+        let num_lines = self.lines.num_lines();
+        match self.lines.line(num_lines.wrapping_sub(1)) {
+            Some(last) if last.content_end == last.end => {
+                let column =
+                    self.lines
+                        .line_len_with_encoding(self.source, num_lines, self.encoding)?;
+                Ok(Position::new(to_u32(num_lines - 1), to_u32(column)))
+            }
+            _ => Ok(Position::new(to_u32(num_lines), 0)),
+        }
+    }
+
+    /// Convert an LSP position into a byte offset.
+    pub fn to_offset(&self, position: Position) -> Result<usize, LinesError> {
+        let len = self.lines.total_len();
+        let index = position.line as usize;
+        let character = position.character as usize;
+        let content_end = match self.lines.line(index) {
+            Some(line) => line.content_end,
+            // The empty line following a final line terminator.
+            None if self.is_final_line(index) => len,
+            None => match self.policy {
+                ClampPolicy::Strict => return Err(LinesError::LineOutOfBounds),
+                ClampPolicy::Clamp => return Ok(len),
+            },
+        };
+        let offset = match index < self.lines.num_lines() {
+            true => {
+                self.lines
+                    .offset_of_with_encoding(self.source, index + 1, character, self.encoding)
+            }
+            false if character == 0 => Ok(len),
+            false => Err(LinesError::ColumnOutOfBounds),
+        };
+        match (offset, self.policy) {
+            (Err(LinesError::ColumnOutOfBounds), ClampPolicy::Clamp) => Ok(content_end),
+            (offset, _) => offset,
+        }
+    }
+
+    /// Whether a zero-indexed line is the empty line after the end of the parsed lines.
+    fn is_final_line(&self, index: usize) -> bool {
+        let num_lines = self.lines.num_lines();
+        index == num_lines
+            && self
+                .lines
+                .line(num_lines.wrapping_sub(1))
+                .is_none_or(|last| last.content_end < last.end)
+    }
+}
+
+/// Build the line-based levels of an LSP [SelectionRange] chain around an offset of `source`, the
+/// text `lines` was built from, with characters counted in the given encoding.
+///
+/// From the innermost, the levels are the offset itself, the content of its line, the paragraph
+/// of adjacent non-blank lines around it, and the whole document. Levels that would repeat the
+/// previous one are skipped. Servers can use this until they have syntax-aware expansion.
+///
+/// ```
+/// use line_position::{lsp::selection_range, Lines, PositionEncoding};
+/// use lsp_types::Position;
+///
+/// let source = "a\nbc\n\nd\n";
+/// let lines = Lines::parse(source);
+/// let selection = selection_range(&lines, source, PositionEncoding::Utf16, 3).unwrap();
+/// assert_eq!(selection.range.start, Position::new(1, 1));
+/// let line = selection.parent.unwrap();
+/// assert_eq!((line.range.start, line.range.end), (Position::new(1, 0), Position::new(1, 2)));
+/// let paragraph = line.parent.unwrap();
+/// assert_eq!(paragraph.range.start, Position::new(0, 0));
+/// let document = paragraph.parent.unwrap();
+/// assert_eq!(document.range.end, Position::new(4, 0));
+/// assert!(document.parent.is_none());
+/// ```
+pub fn selection_range(
+    lines: &Lines,
+    source: &str,
+    encoding: PositionEncoding,
+    offset: usize,
+) -> Result<SelectionRange, LinesError> {
+    let converter = RangeConverter::new(lines, source, encoding, ClampPolicy::Strict);
+    let mut levels = Vec::with_capacity(4);
+    levels.push(offset..offset);
+    let index = match offset < lines.total_len() {
+        true => Some(lines.position(offset)?.line() - 1),
+        false => lines
+            .line(lines.num_lines().wrapping_sub(1))
+            .filter(|last| last.content_end == last.end)
+            .map(|_| lines.num_lines() - 1),
+    };
+    if let Some(index) = index {
+        let blank = |index: usize| {
+            lines.line(index).is_none_or(|line| {
+                source
+                    .get(line.start..line.content_end)
+                    .is_none_or(|content| content.trim().is_empty())
+            })
+        };
+        let line = lines.line(index).ok_or(LinesError::LineOutOfBounds)?;
+        levels.push(line.start..line.content_end);
+        if !blank(index) {
+            let first = (0..index).rev().take_while(|&i| !blank(i)).last();
+            let last = (index + 1..lines.num_lines())
+                .take_while(|&i| !blank(i))
+                .last();
+            let start = lines
+                .line(first.unwrap_or(index))
+                .map_or(0, |line| line.start);
+            let end = lines
+                .line(last.unwrap_or(index))
+                .map_or(0, |line| line.content_end);
+            levels.push(start..end);
+        }
+    }
+    levels.push(0..lines.total_len());
+    levels.dedup();
+
+    let mut selection = None;
+    for range in levels.into_iter().rev() {
+        selection = Some(SelectionRange {
+            range: converter.to_lsp(range)?,
+            parent: selection.map(Box::new),
+        });
+    }
+    selection.ok_or(LinesError::OffsetOutOfBounds {
+        offset,
+        max: lines.total_len(),
+    })
+}
+
+/// Compute LSP [FoldingRange]s from indentation, as in [Lines::folding_ranges_by_indent].
+///
+/// The ranges cover whole lines, so they carry no characters.
+pub fn folding_ranges_by_indent(
+    lines: &Lines,
+    source: &str,
+    tab_width: usize,
+) -> Vec<FoldingRange> {
+    lines
+        .folding_ranges_by_indent(source, tab_width)
+        .into_iter()
+        .map(|range| FoldingRange {
+            start_line: to_u32(range.start() - 1),
+            end_line: to_u32(range.end() - 1),
+            ..FoldingRange::default()
+        })
+        .collect()
+}
+
+/// Saturating conversion for the `u32` fields used by the protocol.
+fn to_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+/// Builder for an LSP [Diagnostic] whose ranges are given as byte offsets.
+///
+/// Each range is converted by the [RangeConverter] of its document, so characters are counted
+/// in the converter's encoding and endpoints outside the input follow its policy. All
+/// conversions happen in [build](DiagnosticBuilder::build).
+///
+/// ```
+/// use line_position::lsp::{ClampPolicy, DiagnosticBuilder, RangeConverter};
+/// use line_position::{Lines, PositionEncoding};
+/// use lsp_types::DiagnosticSeverity;
+///
+/// let source = "let é = 1;\nlet é = 2;\n";
+/// let lines = Lines::parse(source);
+/// let converter =
+///     RangeConverter::new(&lines, source, PositionEncoding::Utf16, ClampPolicy::Strict);
+/// let severity = DiagnosticSeverity::WARNING;
+/// let diagnostic = DiagnosticBuilder::new(converter, 16..18, severity, "shadowed")
+///     .source("lint")
+///     .build()
+///     .unwrap();
+/// assert_eq!(diagnostic.range.start.line, 1);
+/// assert_eq!(diagnostic.range.start.character, 4);
+/// assert_eq!(diagnostic.range.end.character, 5);
+/// ```
+#[derive(Debug)]
+pub struct DiagnosticBuilder<'a> {
+    converter: RangeConverter<'a>,
+    range: Range<usize>,
+    severity: DiagnosticSeverity,
+    message: String,
+    code: Option<NumberOrString>,
+    source: Option<String>,
+    tags: Vec<DiagnosticTag>,
+    related: Vec<Related<'a>>,
+}
+
+#[derive(Debug)]
+struct Related<'a> {
+    uri: Uri,
+    converter: RangeConverter<'a>,
+    range: Range<usize>,
+    message: String,
+}
+
+impl<'a> DiagnosticBuilder<'a> {
+    /// Start a diagnostic covering the given byte range of the document `converter` is for.
+    pub fn new(
+        converter: RangeConverter<'a>,
+        range: Range<usize>,
+        severity: DiagnosticSeverity,
+        message: impl Into<String>,
+    ) -> Self {
+        DiagnosticBuilder {
+            converter,
+            range,
+            severity,
+            message: message.into(),
+            code: None,
+            source: None,
+            tags: Vec::new(),
+            related: Vec::new(),
+        }
+    }
+
+    /// Set the diagnostic code.
+    pub fn code(mut self, code: NumberOrString) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Set the human-readable source of the diagnostic, e.g. the name of the tool.
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Add a tag to the diagnostic.
+    pub fn tag(mut self, tag: DiagnosticTag) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Add related information at a byte range of another (or the same) document, converted by
+    /// the [RangeConverter] for that document.
+    pub fn related(
+        mut self,
+        uri: Uri,
+        converter: RangeConverter<'a>,
+        range: Range<usize>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.related.push(Related {
+            uri,
+            converter,
+            range,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Convert all ranges and produce the [Diagnostic].
+    pub fn build(self) -> Result<Diagnostic, LinesError> {
+        let related_information = self
+            .related
+            .into_iter()
+            .map(|related| {
+                Ok(DiagnosticRelatedInformation {
+                    location: Location::new(related.uri, related.converter.to_lsp(related.range)?),
+                    message: related.message,
+                })
+            })
+            .collect::<Result<Vec<_>, LinesError>>()?;
+
+        Ok(Diagnostic {
+            range: self.converter.to_lsp(self.range)?,
+            severity: Some(self.severity),
+            code: self.code,
+            code_description: None,
+            source: self.source,
+            message: self.message,
+            related_information: (!related_information.is_empty()).then_some(related_information),
+            tags: (!self.tags.is_empty()).then_some(self.tags),
+            data: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_conversions() -> Result<(), LinesError> {
+        let range = PositionRange::new(LinePosition::new(1, 0), LinePosition::new(3, 7));
+        let lsp = lsp_types::Range::try_from(range)?;
+        assert_eq!(
+            lsp,
+            lsp_types::Range::new(Position::new(0, 0), Position::new(2, 7))
+        );
+
+        let range = PositionRange::from(lsp);
+        assert_eq!((range.start().line(), range.start().offset()), (1, 0));
+        assert_eq!((range.end().line(), range.end().offset()), (3, 7));
+
+        assert!(matches!(
+            Position::try_from(LinePosition::new(0, 0)),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            Position::try_from(LinePosition::new(1, usize::MAX)),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn apply_changes() -> Result<(), LinesError> {
+        let change =
+            |range: Option<(u32, u32, u32, u32)>, text: &str| TextDocumentContentChangeEvent {
+                range: range.map(|(a, b, c, d)| {
+                    lsp_types::Range::new(Position::new(a, b), Position::new(c, d))
+                }),
+                range_length: None,
+                text: text.to_string(),
+            };
+        let mut document = TextDocument::new("");
+
+        document.apply_changes(
+            1,
+            &[
+                change(None, "aé\n"),
+                change(Some((1, 0, 1, 0)), "b"),
+                change(Some((0, 1, 0, 2)), "e"),
+            ],
+            PositionEncoding::Utf32,
+        )?;
+        assert_eq!((document.text(), document.version()), ("ae\nb", 1));
+
+        assert!(matches!(
+            document.apply_changes(
+                2,
+                &[change(Some((2, 0, 2, 0)), "c")],
+                PositionEncoding::Utf8
+            ),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            document.apply_changes(
+                2,
+                &[change(Some((1, 2, 1, 2)), "c")],
+                PositionEncoding::Utf8
+            ),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert_eq!(document.version(), 1);
+
+        document.apply_changes(
+            2,
+            &[
+                change(Some((1, 1, 1, 1)), "\n"),
+                change(Some((2, 0, 2, 0)), "c"),
+            ],
+            PositionEncoding::Utf16,
+        )?;
+        assert_eq!((document.text(), document.version()), ("ae\nb\nc", 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn strict_round_trip() -> Result<(), LinesError> {
+        let source = "abc\r\ndef\r\nghi";
+        let lines = Lines::parse(source);
+        let converter =
+            RangeConverter::new(&lines, source, PositionEncoding::Utf8, ClampPolicy::Strict);
+
+        for range in [0..0, 0..3, 2..7, 5..10, 10..13, 13..13] {
+            let lsp = converter.to_lsp(range.clone())?;
+            assert_eq!(converter.from_lsp(lsp)?, range, "round trip");
+        }
+        assert_eq!(
+            converter.to_position(13)?,
+            Position::new(2, 3),
+            "end of input"
+        );
+        assert!(converter.to_position(14).is_err(), "out of bounds");
+        assert!(
+            converter.to_offset(Position::new(0, 4)).is_err(),
+            "past end of line"
+        );
+        assert!(
+            converter.to_offset(Position::new(3, 0)).is_err(),
+            "no line after unterminated last line"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn clamp_policy() -> Result<(), LinesError> {
+        let source = "abc\ndef\n";
+        let lines = Lines::parse(source);
+        let converter =
+            RangeConverter::new(&lines, source, PositionEncoding::Utf8, ClampPolicy::Clamp);
+
+        assert_eq!(
+            converter.to_position(8)?,
+            Position::new(2, 0),
+            "end of input"
+        );
+        assert_eq!(
+            converter.to_position(100)?,
+            Position::new(2, 0),
+            "clamped offset"
+        );
+        assert_eq!(
+            converter.to_offset(Position::new(1, 10))?,
+            7,
+            "clamped column"
+        );
+        assert_eq!(
+            converter.to_offset(Position::new(2, 5))?,
+            8,
+            "clamped final line"
+        );
+        assert_eq!(converter.to_offset(Position::new(9, 0))?, 8, "clamped line");
+
+        Ok(())
+    }
+
+    #[test]
+    fn non_ascii_and_final_crlf() -> Result<(), LinesError> {
+        let source = "aé😀\r\nb\r\n";
+        let lines = Lines::parse(source);
+
+        for policy in [ClampPolicy::Strict, ClampPolicy::Clamp] {
+            let converter = RangeConverter::new(&lines, source, PositionEncoding::Utf16, policy);
+            for range in [0..3, 1..7, 3..9, 9..12, 12..12] {
+                let lsp = converter.to_lsp(range.clone())?;
+                assert_eq!(
+                    converter.from_lsp(lsp)?,
+                    range,
+                    "round trip with {policy:?}"
+                );
+            }
+            assert_eq!(converter.to_position(3)?, Position::new(0, 2), "after é");
+            assert_eq!(
+                converter.to_position(7)?,
+                Position::new(0, 4),
+                "after emoji"
+            );
+            assert_eq!(
+                converter.to_position(12)?,
+                Position::new(2, 0),
+                "end of input"
+            );
+            assert_eq!(
+                converter.to_offset(Position::new(0, 3))?,
+                3,
+                "inside a surrogate pair"
+            );
+            assert_eq!(converter.to_offset(Position::new(2, 0))?, 12);
+        }
+
+        let strict =
+            RangeConverter::new(&lines, source, PositionEncoding::Utf16, ClampPolicy::Strict);
+        assert!(matches!(
+            strict.to_offset(Position::new(0, 5)),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert!(matches!(
+            strict.to_offset(Position::new(2, 1)),
+            Err(LinesError::ColumnOutOfBounds)
+        ));
+        assert!(matches!(
+            strict.to_offset(Position::new(3, 0)),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(strict.to_position(13).is_err());
+
+        let clamp =
+            RangeConverter::new(&lines, source, PositionEncoding::Utf16, ClampPolicy::Clamp);
+        assert_eq!(clamp.to_offset(Position::new(0, 5))?, 7, "clamped column");
+        assert_eq!(
+            clamp.to_offset(Position::new(2, 1))?,
+            12,
+            "clamped final line"
+        );
+        assert_eq!(clamp.to_offset(Position::new(3, 0))?, 12, "clamped line");
+        assert_eq!(
+            clamp.to_position(13)?,
+            Position::new(2, 0),
+            "clamped offset"
+        );
+
+        let chars =
+            RangeConverter::new(&lines, source, PositionEncoding::Utf32, ClampPolicy::Strict);
+        assert_eq!(chars.to_position(7)?, Position::new(0, 3));
+        assert_eq!(chars.to_offset(Position::new(0, 3))?, 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn skip_bom_round_trip() -> Result<(), LinesError> {
+        let source = "\u{FEFF}ab";
+        let lines = crate::ParseOptions::new().skip_bom(true).parse(source)?;
+
+        for encoding in [
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+            PositionEncoding::Utf32,
+        ] {
+            let converter = RangeConverter::new(&lines, source, encoding, ClampPolicy::Strict);
+            for offset in 3..=5 {
+                let position = converter.to_position(offset)?;
+                assert_eq!(
+                    position,
+                    Position::new(0, offset as u32 - 3),
+                    "{encoding:?}"
+                );
+                assert_eq!(converter.to_offset(position)?, offset, "{encoding:?}");
+            }
+            assert!(converter.to_offset(Position::new(0, 3)).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_input() -> Result<(), LinesError> {
+        let lines = Lines::parse("");
+        let converter =
+            RangeConverter::new(&lines, "", PositionEncoding::Utf16, ClampPolicy::Strict);
+
+        assert_eq!(converter.to_position(0)?, Position::new(0, 0));
+        assert_eq!(converter.to_offset(Position::new(0, 0))?, 0);
+
+        Ok(())
+    }
+
+    /// Flatten a selection range chain from the innermost level.
+    fn levels(selection: SelectionRange) -> Vec<((u32, u32), (u32, u32))> {
+        let mut levels = Vec::new();
+        let mut selection = Some(Box::new(selection));
+        while let Some(level) = selection {
+            let range = level.range;
+            levels.push((
+                (range.start.line, range.start.character),
+                (range.end.line, range.end.character),
+            ));
+            selection = level.parent;
+        }
+        levels
+    }
+
+    #[test]
+    fn selection_ranges() -> Result<(), LinesError> {
+        let source = "one\r\ntwo\r\n  \r\nthree";
+        let lines = Lines::parse(source);
+
+        assert_eq!(
+            levels(selection_range(&lines, source, PositionEncoding::Utf8, 6)?),
+            [
+                ((1, 1), (1, 1)),
+                ((1, 0), (1, 3)),
+                ((0, 0), (1, 3)),
+                ((0, 0), (3, 5))
+            ]
+        );
+        assert_eq!(
+            levels(selection_range(&lines, source, PositionEncoding::Utf8, 11)?),
+            [((2, 1), (2, 1)), ((2, 0), (2, 2)), ((0, 0), (3, 5))],
+            "blank lines have no paragraph"
+        );
+        assert_eq!(
+            levels(selection_range(&lines, source, PositionEncoding::Utf8, 19)?),
+            [((3, 5), (3, 5)), ((3, 0), (3, 5)), ((0, 0), (3, 5))],
+            "the line and paragraph coincide"
+        );
+        assert_eq!(
+            levels(selection_range(
+                &Lines::parse("a\n"),
+                "a\n",
+                PositionEncoding::Utf8,
+                2
+            )?),
+            [((1, 0), (1, 0)), ((0, 0), (1, 0))]
+        );
+        assert!(selection_range(&lines, source, PositionEncoding::Utf8, 20).is_err());
+
+        let source = "x\n😀ab\n";
+        let lines = Lines::parse(source);
+        assert_eq!(
+            levels(selection_range(&lines, source, PositionEncoding::Utf16, 7)?),
+            [
+                ((1, 3), (1, 3)),
+                ((1, 0), (1, 4)),
+                ((0, 0), (1, 4)),
+                ((0, 0), (2, 0))
+            ],
+            "UTF-16 columns"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn folding_ranges() {
+        let source = "a\n  b\n  c\nd\n";
+        let ranges = folding_ranges_by_indent(&Lines::parse(source), source, 4);
+
+        assert_eq!(
+            ranges,
+            [FoldingRange {
+                start_line: 0,
+                end_line: 2,
+                ..FoldingRange::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn diagnostic_with_related_information() -> Result<(), LinesError> {
+        let (main_source, other_source) = ("fn main() {\n    foo();\n}\n", "fn foo() {}\n");
+        let (main, other) = (Lines::parse(main_source), Lines::parse(other_source));
+        let converter = |lines, source| {
+            RangeConverter::new(lines, source, PositionEncoding::Utf16, ClampPolicy::Strict)
+        };
+        let uri: Uri = "file:///other.rs".parse().unwrap();
+
+        let diagnostic = DiagnosticBuilder::new(
+            converter(&main, main_source),
+            16..19,
+            DiagnosticSeverity::ERROR,
+            "bad",
+        )
+        .code(NumberOrString::Number(7))
+        .related(
+            uri.clone(),
+            converter(&other, other_source),
+            3..6,
+            "defined here",
+        )
+        .build()?;
+
+        assert_eq!(
+            diagnostic.range,
+            lsp_types::Range::new(Position::new(1, 4), Position::new(1, 7))
+        );
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostic.code, Some(NumberOrString::Number(7)));
+        let related = diagnostic.related_information.unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.uri, uri);
+        assert_eq!(
+            related[0].location.range,
+            lsp_types::Range::new(Position::new(0, 3), Position::new(0, 6))
+        );
+        assert!(diagnostic.tags.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostic_in_utf16() -> Result<(), LinesError> {
+        let source = "let 😀 = 1;\n";
+        let lines = Lines::parse(source);
+        let converter =
+            |encoding| RangeConverter::new(&lines, source, encoding, ClampPolicy::Strict);
+        let uri: Uri = "file:///main.rs".parse().unwrap();
+
+        let diagnostic = DiagnosticBuilder::new(
+            converter(PositionEncoding::Utf16),
+            9..10,
+            DiagnosticSeverity::ERROR,
+            "bad",
+        )
+        .related(uri, converter(PositionEncoding::Utf16), 4..8, "emoji")
+        .build()?;
+        assert_eq!(
+            diagnostic.range,
+            lsp_types::Range::new(Position::new(0, 7), Position::new(0, 8))
+        );
+        assert_eq!(
+            diagnostic.related_information.unwrap()[0].location.range,
+            lsp_types::Range::new(Position::new(0, 4), Position::new(0, 6))
+        );
+
+        let diagnostic = DiagnosticBuilder::new(
+            converter(PositionEncoding::Utf8),
+            9..10,
+            DiagnosticSeverity::ERROR,
+            "bad",
+        )
+        .build()?;
+        assert_eq!(diagnostic.range.start, Position::new(0, 9), "byte columns");
+
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostic_ending_at_end_of_input() -> Result<(), LinesError> {
+        let lines = Lines::parse("abc\ndef");
+        let converter = RangeConverter::new(
+            &lines,
+            "abc\ndef",
+            PositionEncoding::Utf16,
+            ClampPolicy::Strict,
+        );
+        let diagnostic =
+            DiagnosticBuilder::new(converter, 4..7, DiagnosticSeverity::HINT, "end").build()?;
+
+        assert_eq!(diagnostic.range.end, Position::new(1, 3));
+        assert!(
+            DiagnosticBuilder::new(converter, 4..8, DiagnosticSeverity::HINT, "end")
+                .build()
+                .is_err(),
+            "out of bounds"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Macros for writing positions literally, mostly in tests.
+
+/// Construct a [LinePosition](crate::LinePosition) from a literal `line:offset`.
+///
+/// The line starts with 1 and the offset within the line with 0, as in
+/// [LinePosition::new](crate::LinePosition::new).
+///
+/// ```
+/// use line_position::{pos, Lines};
+///
+/// let position = pos!(12:5);
+/// assert_eq!((position.line(), position.offset()), (12, 5));
+/// ```
+#[macro_export]
+macro_rules! pos {
+    ($line:literal : $offset:literal) => {
+        $crate::LinePosition::new($line, $offset)
+    };
+}
+
+/// Construct a pair of [LinePosition](crate::LinePosition)s from a literal
+/// `line:offset..line:offset`.
+///
+/// ```
+/// use line_position::span;
+///
+/// let (start, end) = span!(2:0..3:4);
+/// assert_eq!((start.line(), start.offset()), (2, 0));
+/// assert_eq!((end.line(), end.offset()), (3, 4));
+/// ```
+#[macro_export]
+macro_rules! span {
+    ($start_line:literal : $start_offset:literal .. $end_line:literal : $end_offset:literal) => {
+        (
+            $crate::pos!($start_line:$start_offset),
+            $crate::pos!($end_line:$end_offset),
+        )
+    };
+}
+
+/// Assert that a byte offset of `source` is at the expected `line:offset`.
+///
+/// The first argument is any [LineIndex](crate::LineIndex) built from `source`. On failure, the
+/// panic message shows both positions and the offending source line with a caret under the
+/// offset.
+///
+/// ```
+/// use line_position::{assert_position, pos, Lines};
+///
+/// let source = "abc\ndef\n";
+/// let lines = Lines::parse(source);
+/// assert_position!(lines, source, 5, 2:1);
+/// assert_position!(lines, source, 0, pos!(1:0));
+/// ```
+#[macro_export]
+macro_rules! assert_position {
+    ($lines:expr, $source:expr, $offset:expr, $line:literal : $line_offset:literal) => {
+        $crate::assert_position!($lines, $source, $offset, $crate::pos!($line:$line_offset))
+    };
+    ($lines:expr, $source:expr, $offset:expr, $expected:expr) => {
+        if let Err(message) =
+            $crate::macros::check_position(&$lines, $source, $offset, $expected)
+        {
+            panic!("{}", message);
+        }
+    };
+}
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+#[doc(hidden)]
+pub fn check_position(
+    lines: &(impl crate::LineIndex + ?Sized),
+    source: &str,
+    offset: usize,
+    expected: crate::LinePosition,
+) -> Result<(), String> {
+    let expected = (expected.line(), expected.offset());
+    let actual = match lines.position(offset) {
+        Ok(position) if (position.line(), position.offset()) == expected => return Ok(()),
+        Ok(position) => format!("{}:{}", position.line(), position.offset()),
+        Err(error) => error.to_string(),
+    };
+    let snippet = crate::format::snippet(lines, source, offset..offset).unwrap_or_default();
+    Err(format!(
+        "assertion failed: position of offset {offset}\n  expected: {}:{}\n    actual: {actual}\n{snippet}",
+        expected.0, expected.1
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Lines;
+
+    #[test]
+    fn assert_position_passes() {
+        let source = "abc\r\ndef\r\n";
+        let lines = Lines::parse(source);
+
+        assert_position!(lines, source, 0, 1:0);
+        assert_position!(lines, source, 6, 2:1);
+        assert_position!(lines, source, 8, crate::pos!(2:3));
+    }
+
+    #[test]
+    fn assert_position_message() {
+        let source = "abc\ndef\n";
+        let lines = Lines::parse(source);
+        let message = super::check_position(&lines, source, 5, pos!(2:2)).unwrap_err();
+
+        assert_eq!(
+            message,
+            "assertion failed: position of offset 5\n  expected: 2:2\n    actual: 2:1\n2 | def\n  |  ^\n"
+        );
+        let message = super::check_position(&lines, source, 9, pos!(2:2)).unwrap_err();
+        assert!(message.contains("actual: offset 9 outside of input bounds of 8"));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected: 1:1")]
+    fn assert_position_panics() {
+        let source = "abc";
+        let lines = Lines::parse(source);
+        assert_position!(lines, source, 0, 1:1);
+    }
+}
@@ -0,0 +1,435 @@
+//! Command-line interface for looking up positions in files.
+#![forbid(unsafe_code)]
+
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{env, fs, process, thread};
+
+use line_position::document::TextDocument;
+use line_position::format::{location, numbered_lines};
+use line_position::Lines;
+
+const USAGE: &str = r#"usage: line-position locate [--watch] FILE OFFSET
+       line-position stats [--watch] FILE
+       line-position resolve FILE [OFFSETS]
+       line-position lines [--watch] [--numbers] FILE LINES
+
+resolve reads offsets one per line from OFFSETS, or standard input, and prints a JSON
+object per offset of the form {"offset":12,"line":2,"column":4}, with lines counted
+from 1 and columns in bytes from 0.
+
+lines prints the lines of FILE given as N, FIRST..END or FIRST..=LAST.
+
+options:
+    -w, --watch      print again whenever FILE changes
+    -n, --numbers    prefix printed lines with their numbers"#;
+
+/// Interval between checks of a watched file.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    /// Print the position of a byte offset.
+    Locate { offset: usize },
+    /// Print the number of lines and bytes.
+    Stats,
+    /// Print the positions of many offsets, read from a file or standard input.
+    Resolve { offsets: Option<PathBuf> },
+    /// Print a range of lines.
+    Lines {
+        lines: RangeInclusive<usize>,
+        numbers: bool,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    path: PathBuf,
+    watch: bool,
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    let result = match (&args.command, args.watch) {
+        (Command::Resolve { offsets }, _) => resolve_offsets(&args.path, offsets.as_deref()),
+        (_, true) => watch(&args),
+        (_, false) => read(&args.path)
+            .and_then(|document| report(&args, &document))
+            .map(|output| println!("{output}")),
+    };
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut watch = false;
+    let mut numbers = false;
+    let mut positional = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-w" | "--watch" => watch = true,
+            "-n" | "--numbers" => numbers = true,
+            option if option.starts_with('-') => return Err(format!("unknown option {option}")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = positional.next().ok_or("missing command")?;
+    if !matches!(command.as_str(), "locate" | "stats" | "resolve" | "lines") {
+        return Err(format!("unknown command {command}"));
+    }
+    let path = positional.next().ok_or("missing file")?.into();
+    let command = match command.as_str() {
+        "locate" => {
+            let offset = positional.next().ok_or("missing offset")?;
+            let offset = offset
+                .parse()
+                .map_err(|_| format!("invalid offset {offset}"))?;
+            Command::Locate { offset }
+        }
+        "resolve" if watch => return Err("resolve does not support --watch".to_string()),
+        "resolve" => Command::Resolve {
+            offsets: positional.next().map(PathBuf::from),
+        },
+        "lines" => {
+            let lines = positional.next().ok_or("missing lines")?;
+            Command::Lines {
+                lines: parse_line_range(&lines)?,
+                numbers,
+            }
+        }
+        _ => Command::Stats,
+    };
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument {extra}"));
+    }
+
+    Ok(Args {
+        command,
+        path,
+        watch,
+    })
+}
+
+/// Parse a line number, a half-open range `FIRST..END` or an inclusive range `FIRST..=LAST`.
+fn parse_line_range(text: &str) -> Result<RangeInclusive<usize>, String> {
+    let invalid = || format!("invalid lines {text}");
+    let number = |number: &str| number.parse::<usize>().map_err(|_| invalid());
+    let range = match (text.split_once("..="), text.split_once("..")) {
+        (Some((first, last)), _) => number(first)?..=number(last)?,
+        (None, Some((first, end))) => {
+            number(first)?..=number(end)?.checked_sub(1).ok_or_else(invalid)?
+        }
+        (None, None) => number(text).map(|line| line..=line)?,
+    };
+    match range.is_empty() {
+        true => Err(invalid()),
+        false => Ok(range),
+    }
+}
+
+/// Output of the command for the current text of the file.
+fn report(args: &Args, document: &TextDocument) -> Result<String, String> {
+    let lines = document.lines();
+    match args.command {
+        Command::Locate { offset } => {
+            let position = lines
+                .position(offset)
+                .map_err(|error| format!("offset {offset}: {error}"))?;
+            Ok(location(&args.path.display().to_string(), &position))
+        }
+        Command::Stats => Ok(format!(
+            "lines: {}\nbytes: {}",
+            lines.num_lines(),
+            document.text().len()
+        )),
+        Command::Resolve { .. } => Err("resolve streams its output".to_string()),
+        Command::Lines { ref lines, numbers } => {
+            let error = |error| format!("lines {}..={}: {error}", lines.start(), lines.end());
+            match numbers {
+                true => numbered_lines(document.lines(), document.text(), lines.clone())
+                    .map(|output| output.trim_end_matches('\n').to_string())
+                    .map_err(error),
+                false => {
+                    let range = document
+                        .lines()
+                        .byte_range_of_lines(lines.clone())
+                        .map_err(error)?;
+                    let text = &document.text()[range];
+                    let text = text.strip_suffix('\n').unwrap_or(text);
+                    Ok(text.strip_suffix('\r').unwrap_or(text).to_string())
+                }
+            }
+        }
+    }
+}
+
+/// Resolve the offsets listed in a file, or standard input, against the file at `path`.
+fn resolve_offsets(path: &Path, offsets: Option<&Path>) -> Result<(), String> {
+    let document = read(path)?;
+    let output = BufWriter::new(io::stdout().lock());
+    let result = match offsets {
+        Some(offsets) => {
+            let file = fs::File::open(offsets)
+                .map_err(|error| format!("{}: {error}", offsets.display()))?;
+            resolve(document.lines(), BufReader::new(file), output)
+        }
+        None => resolve(document.lines(), io::stdin().lock(), output),
+    };
+    result.map_err(|error| error.to_string())
+}
+
+/// Write a JSON record for each offset in `input`, one per line, skipping blank lines.
+///
+/// Offsets that cannot be resolved produce a record with an `error` field instead, so the output
+/// stays aligned with the input.
+fn resolve(lines: &Lines, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match text.parse::<usize>() {
+            Ok(offset) => match lines.position(offset) {
+                Ok(position) => writeln!(
+                    output,
+                    r#"{{"offset":{offset},"line":{},"column":{}}}"#,
+                    position.line(),
+                    position.offset()
+                )?,
+                Err(error) => writeln!(
+                    output,
+                    r#"{{"offset":{offset},"error":{}}}"#,
+                    json_string(&error.to_string())
+                )?,
+            },
+            Err(_) => writeln!(
+                output,
+                r#"{{"input":{},"error":"invalid offset"}}"#,
+                json_string(text)
+            )?,
+        }
+    }
+    output.flush()
+}
+
+/// Quote and escape text as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn read(path: &Path) -> Result<TextDocument, String> {
+    fs::read_to_string(path)
+        .map(TextDocument::new)
+        .map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// Print the report, and again each time the file changes, until the file cannot be read.
+///
+/// Failures of the command itself, such as an offset beyond a file that has yet to grow, are
+/// printed without ending the watch.
+fn watch(args: &Args) -> Result<(), String> {
+    let mut document = read(&args.path)?;
+    let mut last_stamp = stamp(&args.path)?;
+    loop {
+        match report(args, &document) {
+            Ok(output) => println!("{output}"),
+            Err(error) => eprintln!("error: {error}"),
+        }
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current = stamp(&args.path)?;
+            if current != last_stamp {
+                last_stamp = current;
+                break;
+            }
+        }
+        let text = fs::read_to_string(&args.path)
+            .map_err(|error| format!("{}: {error}", args.path.display()))?;
+        update(&mut document, text);
+    }
+}
+
+/// Length and modification time of a file, to notice changes.
+fn stamp(path: &Path) -> Result<(u64, Option<SystemTime>), String> {
+    let metadata = fs::metadata(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+/// Bring the document up to date with the new text of its file, incrementally if the file was
+/// only appended to.
+fn update(document: &mut TextDocument, text: String) {
+    match text.starts_with(document.text()) {
+        true => {
+            let appended = &text[document.text().len()..];
+            document.append(appended);
+        }
+        false => *document = TextDocument::new(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            args(&["locate", "a.txt", "12"]),
+            Ok(Args {
+                command: Command::Locate { offset: 12 },
+                path: "a.txt".into(),
+                watch: false,
+            })
+        );
+        assert_eq!(
+            args(&["--watch", "stats", "a.txt"]),
+            Ok(Args {
+                command: Command::Stats,
+                path: "a.txt".into(),
+                watch: true,
+            })
+        );
+        assert_eq!(
+            args(&["locate", "a.txt", "x"]),
+            Err("invalid offset x".to_string())
+        );
+        assert_eq!(
+            args(&["stats", "a.txt", "b.txt"]),
+            Err("unexpected argument b.txt".to_string())
+        );
+        assert_eq!(args(&["stats", "-q"]), Err("unknown option -q".to_string()));
+        assert_eq!(args(&["find"]), Err("unknown command find".to_string()));
+        assert_eq!(args(&["stats"]), Err("missing file".to_string()));
+        assert_eq!(args(&[]), Err("missing command".to_string()));
+        assert_eq!(
+            args(&["resolve", "a.txt"]),
+            Ok(Args {
+                command: Command::Resolve { offsets: None },
+                path: "a.txt".into(),
+                watch: false,
+            })
+        );
+        assert_eq!(
+            args(&["lines", "-n", "a.txt", "3..5"]),
+            Ok(Args {
+                command: Command::Lines {
+                    lines: 3..=4,
+                    numbers: true,
+                },
+                path: "a.txt".into(),
+                watch: false,
+            })
+        );
+        assert_eq!(
+            args(&["lines", "a.txt", "1..1"]),
+            Err("invalid lines 1..1".to_string())
+        );
+        assert_eq!(
+            args(&["resolve", "-w", "a.txt", "offsets.txt"]),
+            Err("resolve does not support --watch".to_string())
+        );
+    }
+
+    #[test]
+    fn report_and_update() -> Result<(), String> {
+        let locate = args(&["locate", "a.txt", "4"])?;
+        let stats = args(&["stats", "a.txt"])?;
+        let mut document = TextDocument::new("abc");
+
+        assert_eq!(
+            report(&locate, &document),
+            Err("offset 4: offset 4 outside of input bounds of 3".to_string())
+        );
+        update(&mut document, "abc\nde".to_string());
+        assert_eq!(report(&locate, &document)?, "a.txt:2:1");
+        update(&mut document, "xy\nz\n".to_string());
+        assert_eq!(report(&locate, &document)?, "a.txt:2:2");
+        assert_eq!(report(&stats, &document)?, "lines: 2\nbytes: 5");
+
+        Ok(())
+    }
+
+    #[test]
+    fn line_ranges() {
+        assert_eq!(parse_line_range("7"), Ok(7..=7));
+        assert_eq!(parse_line_range("120..160"), Ok(120..=159));
+        assert_eq!(parse_line_range("120..=160"), Ok(120..=160));
+        assert!(parse_line_range("..4").is_err());
+        assert!(parse_line_range("1..0").is_err());
+        assert!(parse_line_range("a").is_err());
+    }
+
+    #[test]
+    fn report_lines() -> Result<(), String> {
+        let document = TextDocument::new("a\r\nb\r\nc\r\n");
+
+        assert_eq!(
+            report(&args(&["lines", "a.txt", "2..=3"])?, &document)?,
+            "b\r\nc"
+        );
+        assert_eq!(
+            report(&args(&["lines", "-n", "a.txt", "2..4"])?, &document)?,
+            "2 | b\n3 | c"
+        );
+        assert_eq!(
+            report(&args(&["lines", "a.txt", "3..=4"])?, &document),
+            Err("lines 3..=4: line outside of input bounds".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_records() -> io::Result<()> {
+        let lines = Lines::parse("abc\r\ndef");
+        let mut output = Vec::new();
+        resolve(&lines, "0\n 6 \n\n9\nx\"y\n".as_bytes(), &mut output)?;
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"offset":0,"line":1,"column":0}"#,
+                "\n",
+                r#"{"offset":6,"line":2,"column":1}"#,
+                "\n",
+                r#"{"offset":9,"error":"offset 9 outside of input bounds of 8"}"#,
+                "\n",
+                r#"{"input":"x\"y","error":"invalid offset"}"#,
+                "\n",
+            )
+        );
+        assert_eq!(json_string("a\tb\\"), r#""a\u0009b\\""#);
+
+        Ok(())
+    }
+}
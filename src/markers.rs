@@ -0,0 +1,161 @@
+//! Markers, such as bookmarks and breakpoints, that can be stored and found again after edits.
+
+use crate::{fingerprint, LinePosition, Lines, LinesError};
+
+/// A remembered position together with a fingerprint of its line, so it can be found again in
+/// text that changed while the marker was stored.
+///
+/// With the `serde` feature, markers can be serialized to persist them across sessions.
+///
+/// ```
+/// use line_position::{markers::Marker, Lines};
+///
+/// let old = "fn a() {}\nfn b() {}\n";
+/// let marker = Marker::new(&Lines::parse(old), old, 13).unwrap();
+///
+/// let new = "// added\nfn a() {}\nfn b() {}\n";
+/// let moved = marker.reanchor(&Lines::parse(new), new).unwrap();
+/// assert_eq!((moved.line(), moved.column(), moved.offset()), (3, 3, 22));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    offset: usize,
+    line: usize,
+    column: usize,
+    line_fingerprint: u64,
+}
+
+impl Marker {
+    /// Create a marker at an offset of `source`, the text `lines` was built from.
+    pub fn new(lines: &Lines, source: &str, offset: usize) -> Result<Self, LinesError> {
+        let position = lines.position(offset)?;
+        let content = line_content(lines, source, position.line() - 1)?;
+        Ok(Marker {
+            offset,
+            line: position.line(),
+            column: position.offset(),
+            line_fingerprint: fingerprint(content),
+        })
+    }
+
+    /// Offset of the marker when it was created or last re-anchored.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Line number of the marker, starting with 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Offset of the marker within its line, starting with 0.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Position of the marker.
+    pub fn position(&self) -> LinePosition {
+        LinePosition::new(self.line, self.column)
+    }
+
+    /// Find the marker again in `source`, the possibly edited text that `lines` was built from.
+    ///
+    /// The marker stays on its line if it still has the same content. Otherwise it moves to the
+    /// nearest line with that content, preferring earlier lines on ties, keeping its column.
+    /// Returns [None] if no line has the content any more.
+    pub fn reanchor(&self, lines: &Lines, source: &str) -> Option<Marker> {
+        let home = self.line - 1;
+        let matches = |index: usize| {
+            line_content(lines, source, index)
+                .is_ok_and(|content| fingerprint(content) == self.line_fingerprint)
+        };
+        let index = (0..=home.max(lines.num_lines()))
+            .flat_map(|distance| [home.checked_sub(distance), home.checked_add(distance)])
+            .flatten()
+            .find(|&index| index < lines.num_lines() && matches(index))?;
+
+        let line = lines.line(index)?;
+        let start = lines.column_start(index);
+        let column = self.column.min(line.content_end - start);
+        Some(Marker {
+            offset: start + column,
+            line: index + 1,
+            column,
+            line_fingerprint: self.line_fingerprint,
+        })
+    }
+}
+
+/// Content of a zero-indexed line of `source`, without its terminator.
+fn line_content<'a>(lines: &Lines, source: &'a str, index: usize) -> Result<&'a str, LinesError> {
+    let line = lines.line(index).ok_or(LinesError::LineOutOfBounds)?;
+    source
+        .get(lines.column_start(index)..line.content_end)
+        .ok_or(LinesError::StaleIndex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reanchor(marker: &Marker, source: &str) -> Option<(usize, usize)> {
+        marker
+            .reanchor(&Lines::parse(source), source)
+            .map(|marker| (marker.line(), marker.column()))
+    }
+
+    #[test]
+    fn skipped_bom() -> Result<(), LinesError> {
+        let source = "\u{FEFF}break here\n";
+        let lines = crate::ParseOptions::new().skip_bom(true).parse(source)?;
+        let marker = Marker::new(&lines, source, 9)?;
+        assert_eq!((marker.line(), marker.column()), (1, 6));
+
+        let moved = marker.reanchor(&lines, source).expect("line is unchanged");
+        assert_eq!((moved.offset(), moved.column()), (9, 6));
+        assert_eq!(
+            reanchor(&marker, "break here\n"),
+            Some((1, 6)),
+            "BOM removed"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reanchor_markers() -> Result<(), LinesError> {
+        let source = "x\nbreak here\nx\n";
+        let marker = Marker::new(&Lines::parse(source), source, 8)?;
+        assert_eq!((marker.line(), marker.column()), (2, 6));
+
+        assert_eq!(reanchor(&marker, source), Some((2, 6)), "unchanged");
+        assert_eq!(
+            reanchor(&marker, "x\r\nbreak here\r\n"),
+            Some((2, 6)),
+            "line endings do not matter"
+        );
+        assert_eq!(
+            reanchor(&marker, "break here\nx\nx\nbreak here\n"),
+            Some((1, 6)),
+            "earlier line wins a tie"
+        );
+        assert_eq!(reanchor(&marker, "x\nx\nx\nx\nx\nbreak here"), Some((6, 6)));
+        assert_eq!(reanchor(&marker, "x\nbreak there\n"), None);
+        assert!(Marker::new(&Lines::parse(source), source, 99).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() -> Result<(), LinesError> {
+        let source = "a\nb\n";
+        let marker = Marker::new(&Lines::parse(source), source, 2)?;
+        let json = serde_json::to_string(&marker).unwrap();
+
+        assert_eq!(serde_json::from_str::<Marker>(&json).unwrap(), marker);
+
+        Ok(())
+    }
+}
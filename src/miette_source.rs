@@ -0,0 +1,112 @@
+//! Snippets for `miette` diagnostics.
+
+use miette::{MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents};
+
+use crate::source::SourceLines;
+
+/// Serves snippets of the text for `miette` diagnostics, finding lines with the index instead of
+/// scanning the text.
+///
+/// Lines and columns of the snippets start with 0, as `miette` expects, and columns count bytes.
+/// Requires the `miette` feature.
+///
+/// ```
+/// use line_position::source::SourceLines;
+/// use miette::{SourceCode, SourceSpan};
+///
+/// let source = SourceLines::new("fn main() {\n    oops\n}\n");
+/// let contents = source.read_span(&SourceSpan::from(16..20), 1, 1).unwrap();
+/// assert_eq!(contents.data(), b"fn main() {\n    oops\n}\n");
+/// assert_eq!((contents.line(), contents.column()), (0, 0));
+/// ```
+impl SourceCode for SourceLines<'_> {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let start = span.offset();
+        let end = start + span.len();
+        if end > self.total_len() {
+            return Err(MietteError::OutOfBounds);
+        }
+        // Offsets at the end of the input belong to the last line.
+        let index = |offset: usize| self.line_of_unchecked(offset) - 1;
+        let first = index(start);
+        let last = match span.len() {
+            0 => first,
+            _ => index(end - 1),
+        };
+
+        let context_first = first.saturating_sub(context_lines_before);
+        let context_last = (last + context_lines_after).min(self.num_lines().saturating_sub(1));
+        let line_start = |index: usize| self.line_starts().get(index).copied().unwrap_or(0);
+        let (snippet_start, line, column) = match context_lines_before {
+            0 => (start, first, start.saturating_sub(self.column_start(first))),
+            _ => (line_start(context_first), context_first, 0),
+        };
+        let snippet_end = match context_lines_after {
+            0 => end,
+            _ => self
+                .line_span(context_last + 1)
+                .map_or(end, |span| span.end),
+        };
+
+        Ok(Box::new(MietteSpanContents::new(
+            &self.text().as_bytes()[snippet_start..snippet_end],
+            (snippet_start..snippet_end).into(),
+            line,
+            column,
+            context_last + 1 - context_first,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_str() {
+        for text in ["a", "ab\ncd\nef\ngh\n", "ab\r\ncd\r\n\r\nef"] {
+            let source = SourceLines::new(text);
+            // `miette` takes a span ending in a terminator to reach into the next line, and counts
+            // a `\r\n` as one column, so spans with ends inside terminators differ.
+            for start in (0..text.len()).filter(|&start| !text[..start].ends_with('\r')) {
+                let ends =
+                    (start + 1..=text.len()).filter(|&end| !text[..end].ends_with(['\r', '\n']));
+                for end in ends {
+                    for context in 0..3 {
+                        let span = SourceSpan::from(start..end);
+                        let ours = source.read_span(&span, context, context).unwrap();
+                        let theirs = text.read_span(&span, context, context).unwrap();
+                        let summary = |contents: &dyn SpanContents| {
+                            (
+                                contents.data().to_vec(),
+                                *contents.span(),
+                                contents.line(),
+                                contents.column(),
+                            )
+                        };
+                        assert_eq!(
+                            summary(&*ours),
+                            summary(&*theirs),
+                            "{text:?} at {start}..{end} with {context} lines"
+                        );
+                    }
+                }
+            }
+        }
+
+        let empty = SourceLines::new("");
+        let contents = empty.read_span(&SourceSpan::from(0..0), 1, 1).unwrap();
+        assert_eq!(contents.data(), b"");
+
+        let source = SourceLines::new("ab");
+        assert!(matches!(
+            source.read_span(&SourceSpan::from(1..3), 0, 0),
+            Err(MietteError::OutOfBounds)
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+//! Indexing of memory-mapped files.
+//!
+//! Along with `unchecked`, this is the only module allowed to use `unsafe`, which mapping a file
+//! requires.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::Lines;
+
+impl Lines {
+    /// Memory-map the file at the given path and index it, returning both the mapping and the
+    /// index.
+    ///
+    /// Unlike [parse_file](Lines::parse_file), the contents are not copied into memory, which
+    /// halves the memory needed for very large files. The contents do not need to be valid
+    /// UTF-8. Requires the `memmap2` feature.
+    ///
+    /// The file must not be modified, by this or any other process, while the mapping is alive;
+    /// the mapped bytes would then change underneath the program. Fails if the file cannot be
+    /// opened or mapped.
+    ///
+    /// ```no_run
+    /// use line_position::Lines;
+    ///
+    /// let (contents, lines) = Lines::from_path_mmap("trace.log").unwrap();
+    /// println!("{} lines in {} bytes", lines.num_lines(), contents.len());
+    /// ```
+    pub fn from_path_mmap(path: impl AsRef<Path>) -> io::Result<(Mmap, Self)> {
+        let file = File::open(path)?;
+        // SAFETY: Mmap::map requires that the file is not modified while mapped, which is passed
+        // on to the caller in the documentation above.
+        let contents = unsafe { Mmap::map(&file)? };
+        let lines = Lines::parse_bytes(&contents);
+        Ok((contents, lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::workspace::tests::temp_dir;
+
+    #[test]
+    fn matches_parse_file() -> io::Result<()> {
+        let path = temp_dir("mmap").join("a.txt");
+        fs::write(&path, "abc\r\ndef\r\n")?;
+
+        let (contents, lines) = Lines::from_path_mmap(&path)?;
+        let (text, parsed) = Lines::parse_file(&path)?;
+        assert_eq!(&contents[..], text.as_bytes());
+        assert_eq!(lines, parsed);
+        assert!(Lines::from_path_mmap(path.with_extension("missing")).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Parallel parsing and lookups using rayon.
+
+use rayon::prelude::*;
+
+use crate::{LineEnding, LinePosition, Lines, LinesError, BOM};
+
+/// Bytes of input scanned by each parallel task.
+const CHUNK_LEN: usize = 1 << 20;
+
+/// Offsets looked up by each parallel task.
+const OFFSETS_PER_TASK: usize = 4096;
+
+impl Lines {
+    /// Parse input like [parse](Lines::parse), scanning chunks of it across threads.
+    ///
+    /// The result is the same as that of [parse](Lines::parse), including for a `\r\n` split
+    /// between chunks. This pays off for inputs of many megabytes, such as large logs; smaller
+    /// inputs are parsed in a single chunk.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let input = "abc\r\ndef\n";
+    /// assert_eq!(Lines::par_parse(input), Lines::parse(input));
+    /// ```
+    pub fn par_parse(input: &str) -> Self {
+        Lines::par_scan(input.as_bytes(), CHUNK_LEN)
+    }
+
+    /// Index the line terminators of `input` in parallel chunks of `chunk_len` bytes.
+    fn par_scan(input: &[u8], chunk_len: usize) -> Self {
+        let chunks: Vec<(Vec<usize>, Vec<LineEnding>)> = input
+            .par_chunks(chunk_len)
+            .enumerate()
+            .map(|(chunk, bytes)| {
+                let base = chunk * chunk_len;
+                memchr::memchr_iter(b'\n', bytes)
+                    .map(|index| {
+                        // Look before the chunk for a `\r` that ends the previous one.
+                        let end = base + index;
+                        let ending = match end.checked_sub(1).map(|before| input[before]) {
+                            Some(b'\r') => LineEnding::Crlf,
+                            _ => LineEnding::Lf,
+                        };
+                        (end + 1, ending)
+                    })
+                    .unzip()
+            })
+            .collect();
+
+        let mut lines = Lines::default();
+        let terminators = chunks.iter().map(|(starts, _)| starts.len()).sum();
+        lines.starts.reserve_exact(terminators + 1);
+        lines.endings.reserve_exact(terminators);
+        lines.starts.push(0);
+        for (starts, endings) in chunks {
+            lines.starts.extend(starts);
+            lines.endings.extend(endings);
+        }
+        // The start after the last terminator is only a line if input follows it.
+        if lines.starts.last() == Some(&input.len()) {
+            lines.starts.pop();
+        }
+        lines.len = input.len();
+        lines.bom = input.starts_with(BOM);
+        lines
+    }
+
+    /// Look up the positions of many offsets across threads, returning the results in the same
+    /// order as `offsets`.
+    ///
+    /// Each result is the same as that of [position](Lines::position) for the offset. This pays
+    /// off for very large batches, such as every token of a workspace; for a few thousand
+    /// offsets, [positions](Lines::positions) is usually faster. Each thread takes a run of
+    /// offsets at a time, so sorted runs get the same single sweep as there.
+    ///
+    /// An index can be shared by the threads of a larger job through an `Arc<Lines>`.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("ab\ncd\n");
+    /// let positions = lines.par_positions(&[4, 0, 9]);
+    /// assert_eq!(positions[0].as_ref().unwrap().line(), 2);
+    /// assert_eq!(positions[1].as_ref().unwrap().line(), 1);
+    /// assert!(positions[2].is_err());
+    /// ```
+    pub fn par_positions(&self, offsets: &[usize]) -> Vec<Result<LinePosition, LinesError>> {
+        offsets
+            .par_chunks(OFFSETS_PER_TASK)
+            .flat_map_iter(|offsets| self.positions(offsets))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sequential_parse() {
+        for input in [
+            "",
+            "a",
+            "\n",
+            "a\r\n",
+            "\u{FEFF}ab\r\n\r\ncd\n\re",
+            "\r\r\n\n",
+        ] {
+            // Small chunks split every `\r\n` between them at some point.
+            for chunk_len in 1..4 {
+                let lines = Lines::par_scan(input.as_bytes(), chunk_len);
+                assert_eq!(
+                    lines,
+                    Lines::parse(input),
+                    "{input:?} in chunks of {chunk_len}"
+                );
+            }
+            assert_eq!(Lines::par_parse(input), Lines::parse(input));
+        }
+    }
+
+    #[test]
+    fn matches_sequential_lookup() {
+        let source = "line\r\n".repeat(10_000);
+        let lines = Lines::parse(&source);
+        let reversed: Vec<usize> = (0..source.len() + 10).rev().step_by(3).collect();
+        let sorted: Vec<usize> = reversed.iter().rev().copied().collect();
+
+        for offsets in [reversed, sorted] {
+            let parallel = lines.par_positions(&offsets);
+            assert_eq!(parallel.len(), offsets.len());
+            for (&offset, result) in offsets.iter().zip(parallel) {
+                assert_eq!(
+                    format!("{result:?}"),
+                    format!("{:?}", lines.position(offset))
+                );
+            }
+        }
+    }
+}
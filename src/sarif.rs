@@ -0,0 +1,154 @@
+//! Conversion of byte ranges into [SARIF](https://sarifweb.azurewebsites.net/) regions.
+//!
+//! Requires the `serde_json` feature.
+
+use std::ops::Range;
+
+use serde_json::{json, Value};
+
+use crate::format::inclusive_positions;
+use crate::{LineIndex, LinesError};
+
+/// Convert a byte range of `source` into a SARIF `region` object.
+///
+/// Lines and columns are one-indexed and columns count UTF-16 code units, the `columnKind` that
+/// SARIF assumes unless a run sets another. As the SARIF specification requires, `endLine` is the
+/// line containing the last character of the range, while `endColumn` is one past the column of
+/// that character. An empty range produces an insertion point, where the start and end columns
+/// are equal. Offsets inside a character are moved to include the whole character.
+///
+/// The region also carries `byteOffset` and `byteLength`, which describe the range exactly
+/// regardless of the column kind a consumer assumes.
+///
+/// Fails with [StaleIndex](LinesError::StaleIndex) if `source` is not the text `lines` was built
+/// from.
+///
+/// ```
+/// use line_position::{sarif::region, Lines};
+///
+/// let source = "abc\n😀ef\n";
+/// let lines = Lines::parse(source);
+/// let region = region(&lines, source, 8..10).unwrap();
+/// assert_eq!(region["startLine"], 2);
+/// assert_eq!(region["startColumn"], 3, "the emoji is two UTF-16 code units");
+/// assert_eq!(region["endLine"], 2);
+/// assert_eq!(region["endColumn"], 5);
+/// ```
+pub fn region(
+    lines: &(impl LineIndex + ?Sized),
+    source: &str,
+    range: Range<usize>,
+) -> Result<Value, LinesError> {
+    let (start, last) = inclusive_positions(lines, range.clone())?;
+    let length = range.end.saturating_sub(range.start);
+    let start_column = utf16_column(source, range.start - start.offset(), range.start, false)?;
+    let end_column = match length {
+        0 => start_column,
+        _ => utf16_column(source, range.end - 1 - last.offset(), range.end, true)?,
+    };
+    Ok(json!({
+        "startLine": start.line(),
+        "startColumn": start_column + 1,
+        "endLine": last.line(),
+        "endColumn": end_column + 1,
+        "byteOffset": range.start,
+        "byteLength": length,
+    }))
+}
+
+/// UTF-16 column of `offset` on a line whose columns start at `line_start`, with an offset inside a
+/// character moved to the end of it if `round_up` is set, or to its start otherwise.
+fn utf16_column(
+    source: &str,
+    line_start: usize,
+    mut offset: usize,
+    round_up: bool,
+) -> Result<usize, LinesError> {
+    if offset > source.len() {
+        return Err(LinesError::StaleIndex);
+    }
+    while !source.is_char_boundary(offset) {
+        match round_up {
+            true => offset += 1,
+            false => offset -= 1,
+        }
+    }
+    let text = source
+        .get(line_start.min(offset)..offset)
+        .ok_or(LinesError::StaleIndex)?;
+    Ok(text.encode_utf16().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lines;
+
+    #[test]
+    fn region_spanning_lines() -> Result<(), LinesError> {
+        let source = "abc\ndef\nghi";
+        let lines = Lines::parse(source);
+        let region = region(&lines, source, 1..9)?;
+
+        assert_eq!(
+            region,
+            json!({
+                "startLine": 1,
+                "startColumn": 2,
+                "endLine": 3,
+                "endColumn": 2,
+                "byteOffset": 1,
+                "byteLength": 8,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn region_insertion_point() -> Result<(), LinesError> {
+        let source = "abc\ndef\n";
+        let lines = Lines::parse(source);
+        let region = region(&lines, source, 4..4)?;
+
+        assert_eq!(region["startLine"], 2);
+        assert_eq!(region["endLine"], 2);
+        assert_eq!(region["startColumn"], 1);
+        assert_eq!(region["endColumn"], 1);
+        assert_eq!(region["byteLength"], 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn region_utf16_columns() -> Result<(), LinesError> {
+        // "é" is two bytes but one UTF-16 code unit, "😀" four bytes but two code units.
+        let source = "é😀x\n";
+        let lines = Lines::parse(source);
+
+        let emoji = region(&lines, source, 2..6)?;
+        assert_eq!(emoji["startColumn"], 2);
+        assert_eq!(emoji["endColumn"], 4, "one past the emoji");
+        assert_eq!(emoji["byteOffset"], 2);
+        assert_eq!(emoji["byteLength"], 4);
+        assert_eq!(
+            region(&lines, source, 3..4)?["endColumn"],
+            4,
+            "inside the emoji"
+        );
+        assert_eq!(region(&lines, source, 6..6)?["startColumn"], 4);
+        assert_eq!(region(&lines, source, 6..7)?["endColumn"], 5);
+
+        let skipped = crate::ParseOptions::new()
+            .skip_bom(true)
+            .parse("\u{FEFF}ab")?;
+        assert_eq!(region(&skipped, "\u{FEFF}ab", 4..5)?["startColumn"], 2);
+
+        assert!(matches!(
+            region(&lines, "é", 2..7),
+            Err(LinesError::StaleIndex)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+//! An index bundled with the borrowed text it was built from.
+
+use core::ops::{Deref, Range};
+
+use crate::{check_range, Lines, LinesError};
+
+/// A [Lines] index together with a borrow of the text it was built from, so lines can be sliced
+/// without passing the text separately.
+///
+/// Nothing is copied: slices borrow from the original text for its whole lifetime, not just
+/// that of the `SourceLines`. The index methods of [Lines] are available through [Deref].
+///
+/// ```
+/// use line_position::source::SourceLines;
+///
+/// let text = String::from("fn main() {\n    run();\n}\n");
+/// let source = SourceLines::new(&text);
+/// assert_eq!(source.line_str(2).unwrap(), "    run();");
+/// assert_eq!(source.slice(16..19).unwrap(), "run");
+/// assert_eq!(source.position(16).unwrap().line(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SourceLines<'a> {
+    text: &'a str,
+    lines: Lines,
+}
+
+impl<'a> SourceLines<'a> {
+    /// Index borrowed text.
+    pub fn new(text: &'a str) -> Self {
+        SourceLines {
+            text,
+            lines: Lines::parse(text),
+        }
+    }
+
+    /// The indexed text.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The index of the text.
+    pub fn lines(&self) -> &Lines {
+        &self.lines
+    }
+
+    /// Content of a line, starting with 1, without its terminator.
+    ///
+    /// Fails with [LineOutOfBounds](LinesError::LineOutOfBounds) if the line does not exist.
+    pub fn line_str(&self, line: usize) -> Result<&'a str, LinesError> {
+        self.lines.content_of_lines(self.text, line..=line)
+    }
+
+    /// Text of a line, starting with 1, including its terminator.
+    ///
+    /// Fails as [line_str](SourceLines::line_str) does.
+    pub fn line_with_terminator(&self, line: usize) -> Result<&'a str, LinesError> {
+        self.lines.text_of_lines(self.text, line..=line)
+    }
+
+    /// Slice a byte range of the text.
+    ///
+    /// Fails with [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if the range is not within
+    /// the text, or [NotCharBoundary](LinesError::NotCharBoundary) if it does not lie on
+    /// character boundaries.
+    pub fn slice(&self, range: Range<usize>) -> Result<&'a str, LinesError> {
+        check_range(self.text, &range)?;
+        Ok(&self.text[range])
+    }
+}
+
+impl Deref for SourceLines<'_> {
+    type Target = Lines;
+
+    fn deref(&self) -> &Lines {
+        &self.lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_outlive_source_lines() -> Result<(), LinesError> {
+        let text = "a\r\nbé\r\n";
+        let (line, slice) = {
+            let source = SourceLines::new(text);
+            (source.line_str(2)?, source.slice(3..6)?)
+        };
+        assert_eq!((line, slice), ("bé", "bé"));
+
+        let source = SourceLines::new(text);
+        assert_eq!(source.line_with_terminator(1)?, "a\r\n");
+        assert_eq!(source.num_lines(), 2);
+        assert!(matches!(
+            source.line_str(3),
+            Err(LinesError::LineOutOfBounds)
+        ));
+        assert!(matches!(
+            source.slice(3..5),
+            Err(LinesError::NotCharBoundary { offset: 5 })
+        ));
+
+        Ok(())
+    }
+}
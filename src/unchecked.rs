@@ -0,0 +1,66 @@
+//! Lookups without bounds checks, for hot paths that already know their offsets are valid.
+//!
+//! Along with `mmap`, this is the only module allowed to use `unsafe`.
+
+use crate::{LinePosition, Lines};
+
+impl Lines {
+    /// Lookup the position of an offset like [position](Lines::position), without checking that
+    /// it is within the input.
+    ///
+    /// Debug builds still check the offset and panic if it is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// `offset` must be less than the length of the parsed input.
+    ///
+    /// ```
+    /// use line_position::Lines;
+    ///
+    /// let lines = Lines::parse("ab\ncd");
+    /// // SAFETY: 4 is within the 5 bytes of input.
+    /// let position = unsafe { lines.position_unchecked(4) };
+    /// assert_eq!((position.line(), position.offset()), (2, 1));
+    /// ```
+    pub unsafe fn position_unchecked(&self, offset: usize) -> LinePosition {
+        debug_assert!(offset < self.total_len(), "offset {offset} out of bounds");
+        // SAFETY: the offset is within the input, so some line contains it.
+        let index = unsafe { self.line_index(offset).unwrap_unchecked() };
+        LinePosition {
+            line: index + 1,
+            offset: offset.saturating_sub(self.column_start(index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Lines, ParseOptions};
+
+    #[test]
+    fn position_unchecked() {
+        let input = "\u{FEFF}abc\r\nde\r\n\r\nf";
+        let skipped = ParseOptions::new().skip_bom(true).parse(input).unwrap();
+
+        for lines in [Lines::parse(input), skipped] {
+            for offset in 0..input.len() {
+                let checked = lines.position(offset).unwrap();
+                // SAFETY: the offset is within the input.
+                let unchecked = unsafe { lines.position_unchecked(offset) };
+                assert_eq!(
+                    (checked.line(), checked.offset()),
+                    (unchecked.line(), unchecked.offset())
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "offset 1 out of bounds")]
+    fn position_unchecked_debug_check() {
+        let lines = Lines::parse("a");
+        // SAFETY: not actually upheld, but debug builds panic before any undefined behavior.
+        unsafe { lines.position_unchecked(1) };
+    }
+}
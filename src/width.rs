@@ -0,0 +1,121 @@
+//! Display width of text in terminal cells, for aligning carets under diagnostics.
+//!
+//! Requires the `unicode-width` feature.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{Lines, LinesError};
+
+/// How to count characters whose East Asian width is ambiguous, such as `±` or `α`.
+///
+/// Terminals disagree on these, typically rendering them narrow unless configured for a CJK
+/// locale, so the right choice depends on the user's terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmbiguousWidth {
+    /// Count ambiguous characters as one cell.
+    #[default]
+    Narrow,
+    /// Count ambiguous characters as two cells, as CJK terminals do.
+    Wide,
+}
+
+/// Number of terminal cells needed to display `text`.
+///
+/// ```
+/// use line_position::width::{display_width, AmbiguousWidth};
+///
+/// assert_eq!(display_width("日本", AmbiguousWidth::Narrow), 4);
+/// assert_eq!(display_width("±1", AmbiguousWidth::Narrow), 2);
+/// assert_eq!(display_width("±1", AmbiguousWidth::Wide), 3);
+/// ```
+pub fn display_width(text: &str, ambiguous: AmbiguousWidth) -> usize {
+    match ambiguous {
+        AmbiguousWidth::Narrow => text.width(),
+        AmbiguousWidth::Wide => text.width_cjk(),
+    }
+}
+
+impl Lines {
+    /// Display column of a byte offset within its line, counted in terminal cells from 0.
+    ///
+    /// `source` must be the text this index was built from, otherwise this may fail with
+    /// [StaleIndex](LinesError::StaleIndex). An offset inside a multi-byte character is treated as
+    /// the start of that character.
+    ///
+    /// Requires the `unicode-width` feature.
+    pub fn display_column(
+        &self,
+        source: &str,
+        offset: usize,
+        ambiguous: AmbiguousWidth,
+    ) -> Result<usize, LinesError> {
+        let position = self.position(offset)?;
+        let line_start = self.column_start(position.line() - 1);
+        if offset > source.len() {
+            return Err(LinesError::StaleIndex);
+        }
+        let mut boundary = offset;
+        while !source.is_char_boundary(boundary) {
+            boundary -= 1;
+        }
+        // Offsets inside a skipped byte order mark are at column 0.
+        let boundary = boundary.max(line_start);
+        let text = source
+            .get(line_start..boundary)
+            .ok_or(LinesError::StaleIndex)?;
+        Ok(display_width(text, ambiguous))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_column() -> Result<(), LinesError> {
+        let source = "x\n日本±x\n";
+        let lines = Lines::parse(source);
+
+        assert_eq!(lines.display_column(source, 2, AmbiguousWidth::Narrow)?, 0);
+        assert_eq!(lines.display_column(source, 5, AmbiguousWidth::Narrow)?, 2);
+        assert_eq!(lines.display_column(source, 10, AmbiguousWidth::Narrow)?, 5);
+        assert_eq!(lines.display_column(source, 10, AmbiguousWidth::Wide)?, 6);
+        assert_eq!(
+            lines.display_column(source, 6, AmbiguousWidth::Narrow)?,
+            2,
+            "inside a character"
+        );
+        assert!(lines
+            .display_column(source, 12, AmbiguousWidth::Narrow)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn display_column_skipping_bom() -> Result<(), LinesError> {
+        let source = "\u{FEFF}a日b";
+        let lines = crate::ParseOptions::new().skip_bom(true).parse(source)?;
+
+        assert_eq!(
+            lines.display_column(source, 1, AmbiguousWidth::Narrow)?,
+            0,
+            "inside the BOM"
+        );
+        assert_eq!(lines.display_column(source, 3, AmbiguousWidth::Narrow)?, 0);
+        assert_eq!(lines.display_column(source, 7, AmbiguousWidth::Narrow)?, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn display_column_stale_source() {
+        let lines = Lines::parse("ab\ncd");
+        for offset in [3, 4] {
+            assert!(matches!(
+                lines.display_column("ab", offset, AmbiguousWidth::Narrow),
+                Err(LinesError::StaleIndex)
+            ));
+        }
+    }
+}
@@ -0,0 +1,1064 @@
+//! Indexing of many files at once.
+//!
+//! A [Workspace] assigns each file a [FileId] and resolves `(FileId, offset)` pairs to paths,
+//! lines and columns, serving as the source map of a compiler or linter. Files can be read from
+//! disk or added from memory.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::{fs, io, thread};
+
+use crate::{Lines, LinesError};
+
+/// Small, copyable identifier of a file, handed out by a [FileInterner].
+///
+/// Identifiers stay valid when a file is reloaded and are never reused for another file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(u32);
+
+/// Name of a file, as either a filesystem path or a URI.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileKey {
+    /// A filesystem path.
+    Path(PathBuf),
+    /// A URI, such as those used by language server clients.
+    Uri(String),
+}
+
+impl From<PathBuf> for FileKey {
+    fn from(path: PathBuf) -> Self {
+        FileKey::Path(path)
+    }
+}
+
+impl From<&Path> for FileKey {
+    fn from(path: &Path) -> Self {
+        FileKey::Path(path.to_path_buf())
+    }
+}
+
+/// Interner assigning a stable [FileId] to each distinct path or URI.
+///
+/// ```
+/// use std::path::Path;
+/// use line_position::workspace::{FileInterner, FileKey};
+///
+/// let mut interner = FileInterner::new();
+/// let id = interner.intern(Path::new("src/lib.rs"));
+/// assert_eq!(interner.intern(Path::new("src/lib.rs")), id);
+/// assert_eq!(interner.path_id(Path::new("src/lib.rs")), Some(id));
+/// assert_eq!(interner.lookup(id), Some(&FileKey::Path("src/lib.rs".into())));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FileInterner {
+    keys: Vec<FileKey>,
+    paths: HashMap<PathBuf, FileId>,
+    uris: HashMap<String, FileId>,
+}
+
+impl FileInterner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the identifier for a key, assigning a new one if it hasn't been seen before.
+    pub fn intern(&mut self, key: impl Into<FileKey>) -> FileId {
+        let key = key.into();
+        let existing = match &key {
+            FileKey::Path(path) => self.paths.get(path),
+            FileKey::Uri(uri) => self.uris.get(uri),
+        };
+        if let Some(&id) = existing {
+            return id;
+        }
+
+        let id = FileId(u32::try_from(self.keys.len()).expect("too many files"));
+        match &key {
+            FileKey::Path(path) => self.paths.insert(path.clone(), id),
+            FileKey::Uri(uri) => self.uris.insert(uri.clone(), id),
+        };
+        self.keys.push(key);
+        id
+    }
+
+    /// Identifier previously assigned to a path.
+    pub fn path_id(&self, path: impl AsRef<Path>) -> Option<FileId> {
+        self.paths.get(path.as_ref()).copied()
+    }
+
+    /// Identifier previously assigned to a URI.
+    pub fn uri_id(&self, uri: &str) -> Option<FileId> {
+        self.uris.get(uri).copied()
+    }
+
+    /// Key an identifier was assigned to.
+    pub fn lookup(&self, id: FileId) -> Option<&FileKey> {
+        self.keys.get(id.0 as usize)
+    }
+
+    /// Number of interned keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no keys have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// A file held by a [Workspace], with its contents and index.
+#[derive(Debug)]
+pub struct SourceFile {
+    text: String,
+    fingerprint: u64,
+    /// Index of the contents, or `None` if dropped to stay within the memory budget.
+    ///
+    /// Each file has its own lock, so queries of different files never contend.
+    lines: RwLock<Option<Arc<Lines>>>,
+    /// Workspace clock value of the most recent query.
+    last_used: AtomicU64,
+    /// Bytes held by the indexes of the workspace the file is in, shared with it.
+    ///
+    /// Only changed while holding the write lock on `lines`, so it always matches the indexes
+    /// held.
+    usage: Arc<AtomicUsize>,
+}
+
+impl SourceFile {
+    fn new(text: String, lines: Lines) -> Self {
+        let fingerprint = crate::fingerprint(&text);
+        SourceFile::with_fingerprint(text, lines, fingerprint)
+    }
+
+    fn with_fingerprint(text: String, lines: Lines, fingerprint: u64) -> Self {
+        SourceFile {
+            text,
+            fingerprint,
+            lines: RwLock::new(Some(Arc::new(lines))),
+            last_used: AtomicU64::new(0),
+            usage: Arc::default(),
+        }
+    }
+
+    /// [Fingerprint](crate::fingerprint) of the file contents.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Contents of the file.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Index of the file contents, rebuilt from the contents if it had been dropped.
+    pub fn lines(&self) -> Arc<Lines> {
+        if let Some(lines) = self.held_lines() {
+            return lines;
+        }
+        self.lines
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert_with(|| {
+                let lines = Lines::parse(&self.text);
+                self.usage
+                    .fetch_add(lines.memory_usage(), Ordering::Relaxed);
+                Arc::new(lines)
+            })
+            .clone()
+    }
+
+    /// Index of the file contents if it is currently held.
+    fn held_lines(&self) -> Option<Arc<Lines>> {
+        self.lines
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Bytes used by the index if it is currently held, or 0.
+    fn index_size(&self) -> usize {
+        self.lines
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map_or(0, |lines| lines.memory_usage())
+    }
+
+    /// Drop the index, to be rebuilt on the next query.
+    fn drop_index(&self) {
+        let dropped = self
+            .lines
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(lines) = dropped {
+            self.usage
+                .fetch_sub(lines.memory_usage(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Outcome of indexing a batch of files.
+#[derive(Debug, Default)]
+pub struct IndexReport {
+    /// Paths that were read and indexed, in sorted order.
+    pub indexed: Vec<PathBuf>,
+    /// Paths among `indexed` whose stored index was reused rather than rebuilt, in sorted order.
+    pub reused: Vec<PathBuf>,
+    /// Paths among `indexed` whose stored index did not match the file although its fingerprint
+    /// did, so that it was rebuilt instead, in sorted order.
+    pub invalid: Vec<PathBuf>,
+    /// Paths that could not be read, with the reason, in sorted order.
+    pub failed: Vec<(PathBuf, io::Error)>,
+}
+
+/// Position resolved within a workspace file by [resolve](Workspace::resolve).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Located {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Line number of the position, starting with 1.
+    pub line: usize,
+    /// Byte offset of the position within the line, starting with 0.
+    pub column: usize,
+    /// Text of the line, without its terminator.
+    pub line_text: String,
+}
+
+/// Serializable form of a [Workspace], holding the file table, the index of each file and
+/// fingerprints of their contents, but not the contents themselves.
+///
+/// Requires the `serde` feature.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WorkspaceSnapshot {
+    /// Entries indexed by [FileId], so identifiers survive a round trip.
+    files: Vec<SnapshotEntry>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct SnapshotEntry {
+    key: FileKey,
+    /// Fingerprint and index of the file, or `None` if it had been evicted.
+    indexed: Option<(u64, Lines)>,
+}
+
+/// Change to a file reported by a filesystem watcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsEvent {
+    /// A file was created at the path.
+    Created(PathBuf),
+    /// The file at the path was modified.
+    Modified(PathBuf),
+    /// The file at the path was removed.
+    Removed(PathBuf),
+}
+
+/// Collection of indexed files, keyed by path.
+///
+/// A workspace can be shared between threads for concurrent queries. Each file's index sits
+/// behind its own lock, which is only taken for writing when an index dropped under the
+/// [memory budget](Workspace::set_memory_budget) has to be rebuilt.
+///
+/// ```no_run
+/// use line_position::workspace::Workspace;
+///
+/// let mut workspace = Workspace::new();
+/// let report = workspace.index_dir("src", |path| path.extension().is_some_and(|ext| ext == "rs"));
+/// for (path, error) in &report.failed {
+///     eprintln!("{}: {error}", path.display());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Workspace {
+    interner: FileInterner,
+    /// Files indexed by [FileId], or `None` once evicted.
+    files: Vec<Option<SourceFile>>,
+    /// Maximum bytes of per-file indexes to hold, if limited.
+    memory_budget: Option<usize>,
+    /// Counter ordering queries, for finding the least recently used indexes.
+    clock: AtomicU64,
+    /// Bytes currently held by per-file indexes, shared with each [SourceFile].
+    usage: Arc<AtomicUsize>,
+}
+
+impl Workspace {
+    /// Create an empty workspace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walk the directory tree under `root` and index every file for which `matches` returns
+    /// true, in parallel.
+    ///
+    /// Files already in the workspace are replaced. Symbolic links to directories are not
+    /// followed. Directories that cannot be read are reported as failures alongside files.
+    pub fn index_dir(
+        &mut self,
+        root: impl AsRef<Path>,
+        matches: impl Fn(&Path) -> bool,
+    ) -> IndexReport {
+        let mut paths = Vec::new();
+        let mut failed = Vec::new();
+        walk(root.as_ref(), &matches, &mut paths, &mut failed);
+
+        let mut report = self.index_paths(paths);
+        report.failed.append(&mut failed);
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+        report
+    }
+
+    /// Walk the directory tree under `root` like [index_dir](Workspace::index_dir), but skip
+    /// anything excluded by `.gitignore`, `.ignore` and hidden-file rules.
+    ///
+    /// `globs` further filters the walk using gitignore syntax: files must match at least one
+    /// glob without a `!` prefix (if any are given), and anything matching a `!` glob is skipped.
+    /// Gitignore rules apply whether or not `root` is inside a git repository.
+    ///
+    /// Returns an error only if one of the globs is invalid.
+    ///
+    /// Requires the `ignore` feature.
+    #[cfg(feature = "ignore")]
+    pub fn index_dir_ignoring(
+        &mut self,
+        root: impl AsRef<Path>,
+        globs: &[&str],
+    ) -> Result<IndexReport, ignore::Error> {
+        let root = root.as_ref();
+        let mut overrides = ignore::overrides::OverrideBuilder::new(root);
+        for glob in globs {
+            overrides.add(glob)?;
+        }
+        let walker = ignore::WalkBuilder::new(root)
+            .require_git(false)
+            .overrides(overrides.build()?)
+            .build();
+
+        let mut paths = Vec::new();
+        let mut failed = Vec::new();
+        for entry in walker {
+            match entry {
+                Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => {
+                    paths.push(entry.into_path())
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    let path = match &error {
+                        ignore::Error::WithPath { path, .. } => path.clone(),
+                        _ => root.to_path_buf(),
+                    };
+                    let error = error
+                        .into_io_error()
+                        .unwrap_or_else(|| io::Error::other("failed to walk directory"));
+                    failed.push((path, error));
+                }
+            }
+        }
+
+        let mut report = self.index_paths(paths);
+        report.failed.append(&mut failed);
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(report)
+    }
+
+    /// Read and index the given files in parallel.
+    pub(crate) fn index_paths(&mut self, paths: Vec<PathBuf>) -> IndexReport {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = paths.len().div_ceil(threads).max(1);
+
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|path| (path.clone(), Lines::parse_file(path)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("indexing thread panicked"))
+                .collect()
+        });
+
+        let mut report = IndexReport::default();
+        for (path, result) in results {
+            match result {
+                Ok((text, lines)) => {
+                    self.insert(path.clone(), SourceFile::new(text, lines));
+                    report.indexed.push(path);
+                }
+                Err(error) => report.failed.push((path, error)),
+            }
+        }
+        report.indexed.sort();
+        self.enforce_budget(None);
+        report
+    }
+
+    /// Update the workspace for a single filesystem event, touching only the affected file.
+    ///
+    /// A created file is indexed, a modified file is re-indexed and a removed file is evicted.
+    /// Modifications to files that are not in the workspace are ignored, so events for files a
+    /// caller chose not to index don't add them.
+    ///
+    /// If a created or modified file cannot be read, it is evicted and the error is returned.
+    pub fn handle_fs_event(&mut self, event: FsEvent) -> io::Result<()> {
+        let path = match event {
+            FsEvent::Removed(path) => {
+                self.remove(&path);
+                return Ok(());
+            }
+            FsEvent::Modified(path) if self.get(&path).is_none() => return Ok(()),
+            FsEvent::Created(path) | FsEvent::Modified(path) => path,
+        };
+        match Lines::parse_file(&path) {
+            Ok((text, lines)) => {
+                self.insert(path, SourceFile::new(text, lines));
+                self.enforce_budget(None);
+                Ok(())
+            }
+            Err(error) => {
+                self.remove(&path);
+                Err(error)
+            }
+        }
+    }
+
+    /// Add or replace a file whose contents are already in memory, such as generated code or an
+    /// unsaved editor buffer, and index it.
+    ///
+    /// The path is only used as a name and does not need to exist. Returns the identifier of the
+    /// file, which is kept if the path was seen before.
+    ///
+    /// ```
+    /// use line_position::workspace::Workspace;
+    ///
+    /// let mut workspace = Workspace::new();
+    /// let id = workspace.add_file("src/main.rs", "fn main() {\n    oops\n}\n");
+    /// let located = workspace.resolve(id, 16).unwrap();
+    /// assert_eq!((located.line, located.column), (2, 4));
+    /// assert_eq!(located.path.to_str(), Some("src/main.rs"));
+    /// ```
+    pub fn add_file(&mut self, path: impl Into<PathBuf>, text: impl Into<String>) -> FileId {
+        let text = text.into();
+        let lines = Lines::parse(&text);
+        let id = self.insert(path.into(), SourceFile::new(text, lines));
+        self.enforce_budget(None);
+        id
+    }
+
+    /// Resolve a byte offset within a file to its path, line, column and line text.
+    ///
+    /// Fails with [UnknownFile](LinesError::UnknownFile) if the file is not in the workspace, or
+    /// [OffsetOutOfBounds](LinesError::OffsetOutOfBounds) if the offset is beyond its contents.
+    pub fn resolve(&self, id: FileId, offset: usize) -> Result<Located, LinesError> {
+        let file = self.get_by_id(id).ok_or(LinesError::UnknownFile)?;
+        let lines = file.lines();
+        let position = lines.position(offset)?;
+        let line = lines
+            .line(position.line() - 1)
+            .expect("position refers to a parsed line");
+        Ok(Located {
+            path: self
+                .path(id)
+                .expect("indexed files have paths")
+                .to_path_buf(),
+            line: position.line(),
+            column: position.offset(),
+            line_text: file.text[line.start..line.content_end].to_string(),
+        })
+    }
+
+    /// Capture the file table and indexes of the workspace for persisting.
+    ///
+    /// Indexes dropped under the [memory budget](Workspace::set_memory_budget) are rebuilt for
+    /// the snapshot only, so taking one does not count as a query.
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> WorkspaceSnapshot {
+        let files = self
+            .interner
+            .keys
+            .iter()
+            .zip(&self.files)
+            .map(|(key, file)| SnapshotEntry {
+                key: key.clone(),
+                indexed: file.as_ref().map(|file| {
+                    let lines = file
+                        .held_lines()
+                        .map_or_else(|| Lines::parse(&file.text), |lines| Lines::clone(&lines));
+                    (file.fingerprint, lines)
+                }),
+            })
+            .collect();
+        WorkspaceSnapshot { files }
+    }
+
+    /// Rebuild a workspace from a snapshot, re-reading every file from disk.
+    ///
+    /// Files whose contents still match their stored fingerprint reuse the stored index, and
+    /// only changed files are re-indexed. A stored index is first checked against the file with
+    /// [check_invariants](Lines::check_invariants) and [validate](Lines::validate), and rebuilt
+    /// and reported as invalid if it fails, guarding against corrupted snapshots. Files that can no longer be read are reported as
+    /// failures and left out. Identifiers are the same as in the workspace the snapshot was
+    /// taken from.
+    ///
+    /// Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn restore(snapshot: WorkspaceSnapshot) -> (Self, IndexReport) {
+        let mut workspace = Workspace::new();
+        let mut report = IndexReport::default();
+        for entry in snapshot.files {
+            workspace.interner.intern(entry.key.clone());
+            workspace.files.push(None);
+            let (FileKey::Path(path), Some((fingerprint, lines))) = (entry.key, entry.indexed)
+            else {
+                continue;
+            };
+
+            let text = match fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(error) => {
+                    report.failed.push((path, error));
+                    continue;
+                }
+            };
+            let unchanged = crate::fingerprint(&text) == fingerprint;
+            let valid = lines.check_invariants().is_ok() && lines.validate(&text).is_ok();
+            let file = match unchanged && valid {
+                true => {
+                    report.reused.push(path.clone());
+                    SourceFile::with_fingerprint(text, lines, fingerprint)
+                }
+                false => {
+                    if unchanged {
+                        report.invalid.push(path.clone());
+                    }
+                    let lines = Lines::parse(&text);
+                    SourceFile::new(text, lines)
+                }
+            };
+            workspace.insert(path.clone(), file);
+            report.indexed.push(path);
+        }
+        report.indexed.sort();
+        report.reused.sort();
+        report.invalid.sort();
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+        workspace.enforce_budget(None);
+        (workspace, report)
+    }
+
+    /// Limit the memory held by per-file indexes, or remove the limit with `None`.
+    ///
+    /// When the indexes exceed the budget, those of the least recently queried files are
+    /// dropped until they fit, and rebuilt from the file contents when next queried. The budget
+    /// covers only the indexes, not the file contents. The most recently queried file always
+    /// keeps its index, even if it alone exceeds the budget.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory_budget = budget;
+        self.enforce_budget(None);
+    }
+
+    /// Bytes currently held by per-file indexes.
+    pub fn memory_usage(&self) -> usize {
+        self.usage.load(Ordering::Relaxed)
+    }
+
+    /// Drop the least recently used indexes until within budget, never dropping `keep`.
+    ///
+    /// Files are only collected and sorted while the running usage is over budget, so queries
+    /// within budget stay cheap.
+    fn enforce_budget(&self, keep: Option<FileId>) {
+        let Some(budget) = self.memory_budget else {
+            return;
+        };
+        if self.memory_usage() <= budget {
+            return;
+        }
+        let mut resident: Vec<_> = self
+            .files
+            .iter()
+            .enumerate()
+            .filter_map(|(index, file)| {
+                let file = file.as_ref()?;
+                (file.index_size() > 0 && keep != Some(FileId(index as u32))).then_some(file)
+            })
+            .collect();
+        resident.sort_by_key(|file| file.last_used.load(Ordering::Relaxed));
+
+        for file in resident {
+            if self.memory_usage() <= budget {
+                break;
+            }
+            file.drop_index();
+        }
+    }
+
+    /// Look up the identifier of an indexed file by path.
+    pub fn file_id(&self, path: impl AsRef<Path>) -> Option<FileId> {
+        let id = self.interner.path_id(path)?;
+        self.files.get(id.0 as usize)?.as_ref().map(|_| id)
+    }
+
+    /// Path a file identifier was assigned to, even if the file has since been removed.
+    pub fn path(&self, id: FileId) -> Option<&Path> {
+        match self.interner.lookup(id)? {
+            FileKey::Path(path) => Some(path),
+            FileKey::Uri(_) => None,
+        }
+    }
+
+    /// Look up an indexed file by path.
+    ///
+    /// This counts as a query of the file for the [memory budget](Workspace::set_memory_budget).
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&SourceFile> {
+        self.get_by_id(self.interner.path_id(path)?)
+    }
+
+    /// Look up an indexed file by identifier.
+    ///
+    /// This counts as a query of the file for the [memory budget](Workspace::set_memory_budget).
+    pub fn get_by_id(&self, id: FileId) -> Option<&SourceFile> {
+        let file = self.files.get(id.0 as usize)?.as_ref()?;
+        let now = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        file.last_used.store(now, Ordering::Relaxed);
+        if self.memory_budget.is_some() {
+            file.lines();
+            self.enforce_budget(Some(id));
+        }
+        Some(file)
+    }
+
+    /// Number of files in the workspace.
+    pub fn len(&self) -> usize {
+        self.files.iter().flatten().count()
+    }
+
+    /// Whether the workspace holds no files.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add or replace a file, keeping its identifier if the path was seen before.
+    fn insert(&mut self, path: PathBuf, mut file: SourceFile) -> FileId {
+        let id = self.interner.intern(path);
+        self.files.resize_with(self.interner.len(), || None);
+        self.usage.fetch_add(file.index_size(), Ordering::Relaxed);
+        file.usage = self.usage.clone();
+        if let Some(old) = self.files[id.0 as usize].replace(file) {
+            self.usage.fetch_sub(old.index_size(), Ordering::Relaxed);
+        }
+        id
+    }
+
+    /// Evict a file, keeping its identifier reserved for the path.
+    fn remove(&mut self, path: &Path) {
+        if let Some(old) = self
+            .interner
+            .path_id(path)
+            .and_then(|id| self.files[id.0 as usize].take())
+        {
+            self.usage.fetch_sub(old.index_size(), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Recursively collect matching files under `dir`.
+fn walk(
+    dir: &Path,
+    matches: &impl Fn(&Path) -> bool,
+    paths: &mut Vec<PathBuf>,
+    failed: &mut Vec<(PathBuf, io::Error)>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => return failed.push((dir.to_path_buf(), error)),
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                failed.push((dir.to_path_buf(), error));
+                continue;
+            }
+        };
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => walk(&path, matches, paths, failed),
+            Ok(file_type) if file_type.is_file() || path.is_file() => {
+                if matches(&path) {
+                    paths.push(path);
+                }
+            }
+            Ok(_) => {}
+            Err(error) => failed.push((path, error)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Create a fresh, empty directory under the system temporary directory.
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("line-position-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn index_dir() -> io::Result<()> {
+        let dir = temp_dir("index-dir");
+        fs::create_dir_all(dir.join("nested"))?;
+        fs::write(dir.join("a.txt"), "abc\ndef\n")?;
+        fs::write(dir.join("nested/b.txt"), "abc")?;
+        fs::write(dir.join("nested/c.bin"), "ignored")?;
+        fs::write(dir.join("nested/d.txt"), [0xff, 0xfe])?;
+
+        let mut workspace = Workspace::new();
+        let report = workspace.index_dir(&dir, |path| {
+            path.extension().is_some_and(|ext| ext == "txt")
+        });
+
+        assert_eq!(
+            report.indexed,
+            vec![dir.join("a.txt"), dir.join("nested/b.txt")]
+        );
+        assert_eq!(report.failed.len(), 1, "invalid UTF-8 fails");
+        assert_eq!(report.failed[0].0, dir.join("nested/d.txt"));
+        assert_eq!(workspace.len(), 2);
+        let file = workspace.get(dir.join("a.txt")).unwrap();
+        assert_eq!(file.text(), "abc\ndef\n");
+        assert_eq!(file.lines().num_lines(), 2);
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[cfg(feature = "ignore")]
+    #[test]
+    fn index_dir_ignoring() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = temp_dir("index-dir-ignoring");
+        fs::create_dir_all(dir.join("target"))?;
+        fs::create_dir_all(dir.join("src"))?;
+        fs::write(dir.join(".gitignore"), "target/\n")?;
+        fs::write(dir.join("target/out.rs"), "ignored")?;
+        fs::write(dir.join("src/lib.rs"), "abc\n")?;
+        fs::write(dir.join("src/notes.md"), "abc\n")?;
+        fs::write(dir.join("src/generated.rs"), "abc\n")?;
+
+        let mut workspace = Workspace::new();
+        let report = workspace.index_dir_ignoring(&dir, &["*.rs", "!generated.rs"])?;
+
+        assert_eq!(report.indexed, vec![dir.join("src/lib.rs")]);
+        assert!(report.failed.is_empty());
+        assert!(
+            workspace.index_dir_ignoring(&dir, &["a{"]).is_err(),
+            "bad glob"
+        );
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn handle_fs_event() -> io::Result<()> {
+        let dir = temp_dir("handle-fs-event");
+        let a = dir.join("a.txt");
+        let b = dir.join("b.txt");
+        fs::write(&a, "abc\n")?;
+
+        let mut workspace = Workspace::new();
+        workspace.handle_fs_event(FsEvent::Created(a.clone()))?;
+        assert_eq!(workspace.get(&a).unwrap().lines().num_lines(), 1);
+
+        fs::write(&a, "abc\ndef\n")?;
+        workspace.handle_fs_event(FsEvent::Modified(a.clone()))?;
+        assert_eq!(workspace.get(&a).unwrap().lines().num_lines(), 2);
+
+        fs::write(&b, "abc\n")?;
+        workspace.handle_fs_event(FsEvent::Modified(b.clone()))?;
+        assert!(workspace.get(&b).is_none(), "unknown file not added");
+
+        workspace.handle_fs_event(FsEvent::Removed(a.clone()))?;
+        assert!(workspace.is_empty());
+
+        fs::remove_file(&b)?;
+        assert!(workspace.handle_fs_event(FsEvent::Created(b)).is_err());
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn resolve() -> io::Result<()> {
+        let dir = temp_dir("resolve");
+        let a = dir.join("a.txt");
+        fs::write(&a, "abc\r\ndef\r\n")?;
+
+        let mut workspace = Workspace::new();
+        workspace.index_dir(&dir, |_| true);
+        let id = workspace.file_id(&a).unwrap();
+        let located = workspace.resolve(id, 6).unwrap();
+
+        assert_eq!(
+            located,
+            Located {
+                path: a.clone(),
+                line: 2,
+                column: 1,
+                line_text: "def".to_string(),
+            }
+        );
+        assert!(workspace.resolve(id, 10).is_err(), "out of bounds");
+
+        workspace.handle_fs_event(FsEvent::Modified(a.clone()))?;
+        assert_eq!(workspace.file_id(&a), Some(id), "id survives re-indexing");
+        workspace.handle_fs_event(FsEvent::Removed(a.clone()))?;
+        assert!(matches!(
+            workspace.resolve(id, 0),
+            Err(LinesError::UnknownFile)
+        ));
+        assert_eq!(workspace.path(id), Some(a.as_path()), "reverse lookup");
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn add_file() -> Result<(), LinesError> {
+        let mut workspace = Workspace::new();
+        let a = workspace.add_file("a.rs", "x\ny\n");
+        let b = workspace.add_file("b.rs", "z");
+        assert_ne!(a, b);
+        assert_eq!(
+            workspace.add_file("a.rs", "x\r\ny\r\n"),
+            a,
+            "replacing keeps the id"
+        );
+        assert_eq!(workspace.len(), 2);
+
+        let located = workspace.resolve(a, 4)?;
+        assert_eq!(
+            (located.line, located.column, located.line_text.as_str()),
+            (2, 1, "y")
+        );
+        assert!(matches!(
+            workspace.resolve(b, 1),
+            Err(LinesError::OffsetOutOfBounds { offset: 1, max: 1 })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn interner_paths_and_uris() {
+        let mut interner = FileInterner::new();
+        let path = interner.intern(PathBuf::from("a.rs"));
+        let uri = interner.intern(FileKey::Uri("file:///a.rs".to_string()));
+
+        assert_ne!(path, uri, "paths and URIs are distinct keys");
+        assert_eq!(interner.intern(Path::new("a.rs")), path);
+        assert_eq!(interner.uri_id("file:///a.rs"), Some(uri));
+        assert_eq!(interner.path_id("b.rs"), None);
+        assert_eq!(
+            interner.lookup(uri),
+            Some(&FileKey::Uri("file:///a.rs".to_string()))
+        );
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_keeps_budget() {
+        let mut workspace = Workspace::new();
+        let a = workspace.add_file("a.txt", "abc\ndef\n");
+        workspace.add_file("b.txt", "abc\n");
+        workspace.set_memory_budget(Some(0));
+        assert_eq!(workspace.memory_usage(), 0);
+
+        let snapshot = workspace.snapshot();
+        assert_eq!(workspace.memory_usage(), 0, "dropped indexes not rebuilt");
+        let (_, lines) = snapshot.files[a.0 as usize].indexed.as_ref().unwrap();
+        assert_eq!(lines, &Lines::parse("abc\ndef\n"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_and_restore() -> io::Result<()> {
+        let dir = temp_dir("snapshot");
+        let (a, b, c) = (dir.join("a.txt"), dir.join("b.txt"), dir.join("c.txt"));
+        fs::write(&a, "abc\n")?;
+        fs::write(&b, "abc\n")?;
+        fs::write(&c, "abc\n")?;
+
+        let mut workspace = Workspace::new();
+        workspace.index_dir(&dir, |_| true);
+        let b_id = workspace.file_id(&b).unwrap();
+        let json = serde_json::to_string(&workspace.snapshot()).unwrap();
+
+        fs::write(&b, "abc\ndef\n")?;
+        fs::remove_file(&c)?;
+        let snapshot: WorkspaceSnapshot = serde_json::from_str(&json).unwrap();
+        let (restored, report) = Workspace::restore(snapshot);
+
+        assert_eq!(report.indexed, vec![a.clone(), b.clone()]);
+        assert_eq!(report.reused, vec![a.clone()], "unchanged file reused");
+        assert!(report.invalid.is_empty());
+        assert_eq!(report.failed.len(), 1, "removed file fails");
+        assert_eq!(restored.file_id(&b), Some(b_id), "ids preserved");
+        assert_eq!(restored.get(&b).unwrap().lines().num_lines(), 2);
+        assert!(restored.get(&c).is_none());
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn restore_invalid_index() -> io::Result<()> {
+        let dir = temp_dir("restore-invalid");
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        fs::write(&a, "abc\n")?;
+        fs::write(&b, "abc\n")?;
+
+        let mut workspace = Workspace::new();
+        workspace.index_dir(&dir, |_| true);
+        let mut snapshot = workspace.snapshot();
+        let b_id = workspace.file_id(&b).unwrap();
+        let (_, lines) = snapshot.files[b_id.0 as usize].indexed.as_mut().unwrap();
+        *lines = Lines::from_line_starts(vec![0, 2], 4).unwrap();
+        let (restored, report) = Workspace::restore(snapshot);
+
+        assert_eq!(report.indexed, vec![a.clone(), b.clone()]);
+        assert_eq!(report.reused, vec![a.clone()]);
+        assert_eq!(
+            report.invalid,
+            vec![b.clone()],
+            "terminator not in the file"
+        );
+        assert_eq!(
+            *restored.get(&b).unwrap().lines(),
+            Lines::parse("abc\n"),
+            "reparsed"
+        );
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn memory_budget() -> io::Result<()> {
+        let dir = temp_dir("memory-budget");
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            fs::write(dir.join(name), "abc\ndef\nghi\n")?;
+        }
+        let mut workspace = Workspace::new();
+        workspace.index_dir(&dir, |_| true);
+        let ids: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+            .map(|name| workspace.file_id(dir.join(name)).unwrap())
+            .into();
+        let per_file = workspace.memory_usage() / 3;
+
+        workspace.get_by_id(ids[0]);
+        workspace.get_by_id(ids[1]);
+        workspace.get_by_id(ids[2]);
+        workspace.set_memory_budget(Some(per_file * 2));
+        assert_eq!(
+            workspace.memory_usage(),
+            per_file * 2,
+            "least recent dropped"
+        );
+        assert_eq!(
+            workspace.files[ids[0].0 as usize]
+                .as_ref()
+                .unwrap()
+                .index_size(),
+            0
+        );
+
+        let located = workspace.resolve(ids[0], 5).unwrap();
+        assert_eq!(located.line, 2, "dropped index rebuilt");
+        assert_eq!(
+            workspace.files[ids[1].0 as usize]
+                .as_ref()
+                .unwrap()
+                .index_size(),
+            0
+        );
+
+        workspace.set_memory_budget(Some(0));
+        assert_eq!(workspace.memory_usage(), 0);
+        assert_eq!(workspace.resolve(ids[2], 9).unwrap().line_text, "ghi");
+        assert_eq!(workspace.memory_usage(), per_file, "queried file kept");
+
+        workspace.set_memory_budget(None);
+        workspace.add_file(dir.join("a.txt"), "abc\n");
+        workspace.handle_fs_event(FsEvent::Removed(dir.join("c.txt")))?;
+        assert_eq!(
+            workspace.memory_usage(),
+            workspace.get_by_id(ids[0]).unwrap().index_size(),
+            "replaced and removed files uncounted"
+        );
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn concurrent_queries() -> io::Result<()> {
+        fn assert_sync<T: Send + Sync>(_: &T) {}
+
+        let dir = temp_dir("concurrent-queries");
+        for index in 0..8 {
+            fs::write(
+                dir.join(format!("{index}.txt")),
+                "abc\ndef\n".repeat(index + 1),
+            )?;
+        }
+        let mut workspace = Workspace::new();
+        workspace.index_dir(&dir, |_| true);
+        workspace.set_memory_budget(Some(0));
+        assert_sync(&workspace);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for index in 0..8 {
+                        let id = workspace.file_id(dir.join(format!("{index}.txt"))).unwrap();
+                        let located = workspace.resolve(id, index * 8 + 5).unwrap();
+                        assert_eq!(located.line, index * 2 + 2);
+                        assert_eq!(located.line_text, "def");
+                    }
+                });
+            }
+        });
+        let held: usize = workspace
+            .files
+            .iter()
+            .flatten()
+            .map(SourceFile::index_size)
+            .sum();
+        assert_eq!(workspace.memory_usage(), held, "usage counted exactly");
+
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn index_missing_dir() {
+        let mut workspace = Workspace::new();
+        let report = workspace.index_dir("/nonexistent/line-position", |_| true);
+
+        assert!(report.indexed.is_empty());
+        assert_eq!(report.failed.len(), 1);
+        assert!(workspace.is_empty());
+    }
+}